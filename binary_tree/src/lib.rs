@@ -1,4 +1,20 @@
 
+use std::collections::VecDeque;
+
+mod map;
+mod persistent;
+mod render;
+#[cfg(feature = "serde")]
+mod serde_impl;
+mod set;
+mod traversal;
+
+pub use map::{BinaryTreeMap, Entry, OccupiedEntry, VacantEntry};
+pub use persistent::{PersistentIter, PersistentNode, PersistentTree};
+pub use set::{Difference, Intersection, SymmetricDifference, Union};
+pub use traversal::{LevelOrderIter, PostOrderIter, PreOrderIter};
+
+
 /// `BinaryTree`s are either empty or contain a boxed `TreeNode`
 #[derive(Debug)]
 pub enum BinaryTree<T> {
@@ -8,20 +24,206 @@ pub enum BinaryTree<T> {
 
 
 /// Every `TreeNode` contain data, and possibly child `BinaryTree`s.
+/// Each also counts the nodes in its own sub-tree, including itself.
 #[derive(Debug)]
 pub struct TreeNode<T> {
     element: T,
     left: BinaryTree<T>,
     right: BinaryTree<T>,
+    size: usize,
 }
 
 
-impl<T: Ord> BinaryTree<T> {
+impl<T> BinaryTree<T> {
     /// Create new, empty BinaryTree.
     pub fn empty() -> Self {
         BinaryTree::Empty
     }
 
+    /// Is our tree empty?
+    pub fn is_empty(&self) -> bool {
+        matches!(self, BinaryTree::Empty)
+    }
+
+    /// Create iterator over a shared reference
+    pub fn iter(&self) -> TreeIter<'_, T> {
+        // Initialise stack with nodes along left-hand edge
+        let mut iter = TreeIter {
+            unvisited: Vec::new(),
+            unvisited_back: Vec::new(),
+            last_front: None,
+            last_back: None,
+        };
+        iter.push_left_edge(self);
+        iter.push_right_edge(self);
+        iter
+    }
+
+    /// Create iterator over a mutable reference.
+    /// Take care not to change elements in ways that alter their ordering.
+    pub fn iter_mut(&mut self) -> TreeIterMut<'_, T> {
+        TreeIterMut { unvisited: VecDeque::from([Unvisited::Tree(self)]) }
+    }
+
+    /// How many nodes does our tree contain?
+    pub fn len(&self) -> usize {
+        match self {
+            BinaryTree::Empty => 0,
+            BinaryTree::NonEmpty(node) => node.size,
+        }
+    }
+
+    /// The k-th smallest element, counting from zero, in O(height).
+    pub fn select(&self, mut k: usize) -> Option<&T> {
+        let mut tree = self;
+        while let BinaryTree::NonEmpty(ref node) = *tree {
+            let left = node.left.len();
+            if k < left {
+                tree = &node.left;
+            } else if k == left {
+                return Some(&node.element);
+            } else {
+                k -= left + 1;
+                tree = &node.right;
+            }
+        }
+        None
+    }
+
+    /// Build a perfectly balanced tree from elements already in ascending
+    /// order, in O(n) time.
+    pub fn from_sorted_iter<I: IntoIterator<Item=T>>(iter: I) -> Self {
+        let elements: Vec<T> = iter.into_iter().collect();
+        let len = elements.len();
+        BinaryTree::build_balanced(&mut elements.into_iter(), len)
+    }
+
+    /// Number of nodes on the longest path from the root to a leaf.
+    pub fn height(&self) -> usize {
+        self.measure().0
+    }
+
+    /// Do the heights of every node's left and right sub-trees differ by
+    /// no more than one?
+    pub fn is_balanced(&self) -> bool {
+        self.measure().1
+    }
+
+    /// Create new BinaryTree with the given root value.
+    fn new(value: T) -> Self {
+        BinaryTree::<T>::NonEmpty(Box::new(TreeNode {
+            element: value,
+            left: BinaryTree::Empty,
+            right: BinaryTree::Empty,
+            size: 1,
+        }))
+    }
+
+    /// Build balanced tree from the next `len` elements of `iter`.
+    /// Recursion depth is only the height of the new tree, O(log n).
+    fn build_balanced<I: Iterator<Item=T>>(iter: &mut I, len: usize) -> Self {
+        if len == 0 {
+            return BinaryTree::Empty;
+        }
+        let left = BinaryTree::build_balanced(iter, len / 2);
+        let element = iter.next().expect("iterator shorter than given length");
+        let right = BinaryTree::build_balanced(iter, len - len / 2 - 1);
+        BinaryTree::NonEmpty(Box::new(TreeNode { element, left, right, size: len }))
+    }
+
+    /// Calculate height and whether the tree is balanced, in a single
+    /// post-order pass using an explicit stack.
+    fn measure(&self) -> (usize, bool) {
+        let mut balanced = true;
+        let mut unvisited = vec![(self, false)];
+        let mut heights: Vec<usize> = Vec::new();
+        while let Some((tree, expanded)) = unvisited.pop() {
+            match tree {
+                BinaryTree::Empty => heights.push(0),
+                BinaryTree::NonEmpty(_) if expanded => {
+                    let right = heights.pop().unwrap();
+                    let left = heights.pop().unwrap();
+                    balanced &= left.abs_diff(right) <= 1;
+                    heights.push(1 + left.max(right));
+                },
+                BinaryTree::NonEmpty(node) => {
+                    // Left sub-tree is measured first, so its height is
+                    // below the right's on the stack.
+                    unvisited.push((tree, true));
+                    unvisited.push((&node.right, false));
+                    unvisited.push((&node.left, false));
+                },
+            }
+        }
+        (heights.pop().unwrap_or(0), balanced)
+    }
+
+    /// Unlink the root node of this tree and return its element.
+    /// The root's children are stitched back together in its place, using
+    /// the in-order successor if both sides are occupied.
+    ///
+    /// Panics if the tree is empty.
+    fn remove_root(&mut self) -> T {
+        let node = match std::mem::replace(self, BinaryTree::Empty) {
+            BinaryTree::NonEmpty(node) => node,
+            BinaryTree::Empty => panic!("remove_root() called on empty tree"),
+        };
+
+        let TreeNode { element, left, mut right, size } = *node;
+        *self = match (left, &mut right) {
+            (BinaryTree::Empty, _) => right,
+            (left, BinaryTree::Empty) => left,
+            (left, _) => {
+                let successor = right.remove_first();
+                BinaryTree::NonEmpty(Box::new(TreeNode {
+                    element: successor,
+                    left,
+                    right,
+                    size: size - 1,
+                }))
+            },
+        };
+        element
+    }
+
+    /// Unlink the left-most node of this tree and return its element.
+    ///
+    /// Panics if the tree is empty.
+    fn remove_first(&mut self) -> T {
+        let mut tree = self;
+        while let BinaryTree::NonEmpty(ref node) = *tree {
+            if node.left.is_empty() {
+                break;
+            }
+            match tree {
+                BinaryTree::NonEmpty(node) => {
+                    node.size -= 1;
+                    tree = &mut node.left;
+                },
+                BinaryTree::Empty => unreachable!(),
+            }
+        }
+        tree.remove_root()
+    }
+
+    /// Set sizes down one edge of a tree, from the number of nodes `kept`
+    /// by each node on that edge: itself plus its sub-tree off the edge.
+    fn resize_edge(mut tree: &mut BinaryTree<T>, kept: &[usize], rightwards: bool) {
+        let mut size: usize = kept.iter().sum();
+        for k in kept {
+            let node = match tree {
+                BinaryTree::NonEmpty(node) => node,
+                BinaryTree::Empty => unreachable!(),
+            };
+            node.size = size;
+            size -= k;
+            tree = if rightwards { &mut node.right } else { &mut node.left };
+        }
+    }
+}
+
+
+impl<T: Ord> BinaryTree<T> {
     /// Add new value to the tree.
     /// Note that nodes, including the root, are not moved during operation.
     pub fn add(&mut self, value: T) {
@@ -30,6 +232,7 @@ impl<T: Ord> BinaryTree<T> {
                 *self = BinaryTree::new(value)
             },
             BinaryTree::NonEmpty(ref mut node) => {
+                node.size += 1;
                 if value <= node.element {
                     node.left.add(value);
                 } else {
@@ -39,34 +242,78 @@ impl<T: Ord> BinaryTree<T> {
         }
     }
 
-    /// Is our tree empty?
-    pub fn is_empty(&self) -> bool {
-        match self {
-            BinaryTree::Empty => true,
-            _ => false,
+    /// Number of elements strictly less than value, in O(height).
+    pub fn rank(&self, value: &T) -> usize {
+        let mut rank = 0;
+        let mut tree = self;
+        while let BinaryTree::NonEmpty(ref node) = *tree {
+            if *value <= node.element {
+                tree = &node.left;
+            } else {
+                rank += node.left.len() + 1;
+                tree = &node.right;
+            }
         }
+        rank
     }
 
-    /// Create iterator over a shared reference
-    pub fn iter(&self) -> TreeIter<T> {
-        // Initialise stack with nodes along left-hand edge
-        let mut iter = TreeIter { unvisited: Vec::new() };
-        iter.push_left_edge(self);
-        iter
-    }
+    /// Split tree in two, returning a tree of every element greater than or
+    /// equal to `key`. Nodes are relinked rather than copied, along a single
+    /// path from the root.
+    pub fn split_off(&mut self, key: &T) -> Self {
+        let mut rest = std::mem::replace(self, BinaryTree::Empty);
+        let mut low = BinaryTree::Empty;
+        let mut high = BinaryTree::Empty;
+        let mut low_slot = &mut low;
+        let mut high_slot = &mut high;
+
+        // Sizes of the parts kept by each node on the new edges
+        let mut low_kept = Vec::new();
+        let mut high_kept = Vec::new();
+
+        // Every node visited belongs wholly to one side, along with one of
+        // its sub-trees. The other sub-tree still needs to be split.
+        while let BinaryTree::NonEmpty(mut node) = rest {
+            if node.element < *key {
+                rest = std::mem::replace(&mut node.right, BinaryTree::Empty);
+                low_kept.push(node.left.len() + 1);
+                *low_slot = BinaryTree::NonEmpty(node);
+                low_slot = match low_slot {
+                    BinaryTree::NonEmpty(node) => &mut node.right,
+                    BinaryTree::Empty => unreachable!(),
+                };
+            } else {
+                rest = std::mem::replace(&mut node.left, BinaryTree::Empty);
+                high_kept.push(node.right.len() + 1);
+                *high_slot = BinaryTree::NonEmpty(node);
+                high_slot = match high_slot {
+                    BinaryTree::NonEmpty(node) => &mut node.left,
+                    BinaryTree::Empty => unreachable!(),
+                };
+            }
+        }
 
-    /// How many nodes does our tree contain?
-    pub fn len(&self) -> usize {
-        self.iter().count()
+        BinaryTree::resize_edge(&mut low, &low_kept, true);
+        BinaryTree::resize_edge(&mut high, &high_kept, false);
+        *self = low;
+        high
     }
 
-    /// Create new BinaryTree with the given root value.
-    fn new(value: T) -> Self {
-        BinaryTree::<T>::NonEmpty(Box::new(TreeNode {
-            element: value,
-            left: BinaryTree::Empty,
-            right: BinaryTree::Empty,
-        }))
+    /// Move every element of `other` into this tree, leaving `other` empty.
+    /// Both trees are merged in order and rebuilt balanced, in O(n + m).
+    pub fn append(&mut self, other: &mut Self) {
+        let left = std::mem::replace(self, BinaryTree::Empty).into_iter();
+        let right = std::mem::replace(other, BinaryTree::Empty).into_iter();
+        let mut left = left.peekable();
+        let mut right = right.peekable();
+        let merged = std::iter::from_fn(|| {
+            match (left.peek(), right.peek()) {
+                (Some(l), Some(r)) if r < l => right.next(),
+                (Some(_), _) => left.next(),
+                (None, _) => right.next(),
+            }
+        });
+        *self = BinaryTree::from_sorted_iter(merged);
     }
 }
 
@@ -84,7 +331,7 @@ impl<T> FromIterator<T> for BinaryTree<T> where T: Ord {
 
 
 /// Implement the `IntoIterator` trait for `BinaryTree`
-impl<'a, T: 'a> IntoIterator for &'a BinaryTree<T> where T: Ord {
+impl<'a, T: 'a> IntoIterator for &'a BinaryTree<T> {
     type Item = &'a T;
     type IntoIter = TreeIter<'a, T>;
 
@@ -94,12 +341,45 @@ impl<'a, T: 'a> IntoIterator for &'a BinaryTree<T> where T: Ord {
 }
 
 
+/// Implement the `IntoIterator` trait for a mutable `BinaryTree`
+impl<'a, T: 'a> IntoIterator for &'a mut BinaryTree<T> {
+    type Item = &'a mut T;
+    type IntoIter = TreeIterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+
+/// Implement the `IntoIterator` trait for `BinaryTree`, consuming it
+impl<T> IntoIterator for BinaryTree<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { unvisited: VecDeque::from([Unvisited::Tree(self)]) }
+    }
+}
+
+
 /// Iterator over `BinaryTree`.
 /// Structure hold's the current state of this iteration.
+///
+/// Iterating from both ends at once is supported. Each end keeps its own
+/// stack, and iteration finishes when one end reaches the last node produced
+/// by the other.
 #[derive(Debug)]
 pub struct TreeIter<'a, T> {
     // Stack of references to tree nodes.
-    unvisited: Vec<&'a TreeNode<T>>
+    unvisited: Vec<&'a TreeNode<T>>,
+
+    // Stack of references to tree nodes, for iterating in reverse.
+    unvisited_back: Vec<&'a TreeNode<T>>,
+
+    // Most recent nodes produced from the front and back.
+    last_front: Option<&'a TreeNode<T>>,
+    last_back: Option<&'a TreeNode<T>>,
 }
 
 
@@ -111,6 +391,25 @@ impl<'a, T: 'a> TreeIter<'a, T> {
             tree = &node.left;
         }
     }
+
+    /// Walk the right edge of the tree, pushing every node onto the back stack.
+    fn push_right_edge(&mut self, mut tree: &'a BinaryTree<T>) {
+        while let BinaryTree::NonEmpty(ref node) = *tree {
+            self.unvisited_back.push(node);
+            tree = &node.right;
+        }
+    }
+
+    /// Has `node` already been produced by the other end of the iterator?
+    fn is_spent(node: &TreeNode<T>, other: Option<&TreeNode<T>>) -> bool {
+        other.is_some_and(|other| std::ptr::eq(node, other))
+    }
+
+    /// Drop all remaining state once both ends have met.
+    fn finish(&mut self) {
+        self.unvisited.clear();
+        self.unvisited_back.clear();
+    }
 }
 
 impl<'a, T> Iterator for TreeIter<'a, T> {
@@ -119,6 +418,11 @@ impl<'a, T> Iterator for TreeIter<'a, T> {
     fn next(&mut self) -> Option<&'a T> {
         // Find next node or return early
         let node = self.unvisited.pop()?;
+        if Self::is_spent(node, self.last_back) {
+            self.finish();
+            return None;
+        }
+        self.last_front = Some(node);
 
         // After node we must produce left-most child in node's right sub-tree.
         self.push_left_edge(&node.right);
@@ -127,6 +431,145 @@ impl<'a, T> Iterator for TreeIter<'a, T> {
     }
 }
 
+impl<'a, T> DoubleEndedIterator for TreeIter<'a, T> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        // Mirror image of `next()`
+        let node = self.unvisited_back.pop()?;
+        if Self::is_spent(node, self.last_front) {
+            self.finish();
+            return None;
+        }
+        self.last_back = Some(node);
+        self.push_right_edge(&node.left);
+        Some(&node.element)
+    }
+}
+
+
+/// Part of a tree still to be iterated over: a single element, or a whole
+/// sub-tree. Mutable and consuming iterators can't share nodes between their
+/// two ends, as `TreeIter` does, so they keep a queue of disjoint parts in
+/// order instead, splitting sub-trees as either end reaches them.
+#[derive(Debug)]
+enum Unvisited<E, S> {
+    Element(E),
+    Tree(S),
+}
+
+
+/// Iterator over mutable references to the elements of a `BinaryTree`.
+#[derive(Debug)]
+pub struct TreeIterMut<'a, T> {
+    // Queue of unvisited elements and sub-trees, in order.
+    unvisited: VecDeque<Unvisited<&'a mut T, &'a mut BinaryTree<T>>>,
+}
+
+
+impl<'a, T: 'a> TreeIterMut<'a, T> {
+    /// Walk the left edge of the tree, queueing each element and its right
+    /// sub-tree at the front, down to the left-most element.
+    /// Nodes are split into their fields so the left child can be borrowed
+    /// independently of the element and right child.
+    fn push_left_edge(&mut self, mut tree: &'a mut BinaryTree<T>) {
+        while let BinaryTree::NonEmpty(node) = tree {
+            let TreeNode { element, left, right, .. } = &mut **node;
+            self.unvisited.push_front(Unvisited::Tree(right));
+            self.unvisited.push_front(Unvisited::Element(element));
+            tree = left;
+        }
+    }
+
+    /// Mirror image of `push_left_edge()`, queueing at the back.
+    fn push_right_edge(&mut self, mut tree: &'a mut BinaryTree<T>) {
+        while let BinaryTree::NonEmpty(node) = tree {
+            let TreeNode { element, left, right, .. } = &mut **node;
+            self.unvisited.push_back(Unvisited::Tree(left));
+            self.unvisited.push_back(Unvisited::Element(element));
+            tree = right;
+        }
+    }
+}
+
+impl<'a, T> Iterator for TreeIterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        loop {
+            match self.unvisited.pop_front()? {
+                Unvisited::Element(element) => return Some(element),
+                Unvisited::Tree(tree) => self.push_left_edge(tree),
+            }
+        }
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for TreeIterMut<'a, T> {
+    fn next_back(&mut self) -> Option<&'a mut T> {
+        loop {
+            match self.unvisited.pop_back()? {
+                Unvisited::Element(element) => return Some(element),
+                Unvisited::Tree(tree) => self.push_right_edge(tree),
+            }
+        }
+    }
+}
+
+
+/// Consuming iterator over a `BinaryTree`, yielding elements in order.
+#[derive(Debug)]
+pub struct IntoIter<T> {
+    // Queue of unvisited elements and owned sub-trees, in order.
+    unvisited: VecDeque<Unvisited<T, BinaryTree<T>>>,
+}
+
+
+impl<T> IntoIter<T> {
+    /// Walk the left edge of the tree, taking ownership of every node seen,
+    /// and queueing its element and right sub-tree at the front.
+    fn push_left_edge(&mut self, mut tree: BinaryTree<T>) {
+        while let BinaryTree::NonEmpty(node) = tree {
+            let TreeNode { element, left, right, .. } = *node;
+            self.unvisited.push_front(Unvisited::Tree(right));
+            self.unvisited.push_front(Unvisited::Element(element));
+            tree = left;
+        }
+    }
+
+    /// Mirror image of `push_left_edge()`, queueing at the back.
+    fn push_right_edge(&mut self, mut tree: BinaryTree<T>) {
+        while let BinaryTree::NonEmpty(node) = tree {
+            let TreeNode { element, left, right, .. } = *node;
+            self.unvisited.push_back(Unvisited::Tree(left));
+            self.unvisited.push_back(Unvisited::Element(element));
+            tree = right;
+        }
+    }
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        loop {
+            match self.unvisited.pop_front()? {
+                Unvisited::Element(element) => return Some(element),
+                Unvisited::Tree(tree) => self.push_left_edge(tree),
+            }
+        }
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        loop {
+            match self.unvisited.pop_back()? {
+                Unvisited::Element(element) => return Some(element),
+                Unvisited::Tree(tree) => self.push_right_edge(tree),
+            }
+        }
+    }
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -136,7 +579,7 @@ mod tests {
 
     const SINGLE_NODE_EXPECTED: &str = concat!(
         "NonEmpty(TreeNode { element: \"Jupiter\", ",
-        "left: Empty, right: Empty })"
+        "left: Empty, right: Empty, size: 1 })"
     );
 
     const SMALL_TREE_EXPECTED: &str = indoc! {r#"
@@ -148,6 +591,7 @@ mod tests {
                         element: "Jupiter",
                         left: Empty,
                         right: Empty,
+                        size: 1,
                     },
                 ),
                 right: NonEmpty(
@@ -155,12 +599,27 @@ mod tests {
                         element: "Mercury",
                         left: Empty,
                         right: Empty,
+                        size: 1,
                     },
                 ),
+                size: 3,
             },
         )"#
     };
 
+    /// Check every node's size against a recursive count, returning the
+    /// size of the whole tree.
+    pub(crate) fn assert_sizes<T>(tree: &BinaryTree<T>) -> usize {
+        match tree {
+            BinaryTree::Empty => 0,
+            BinaryTree::NonEmpty(node) => {
+                let size = 1 + assert_sizes(&node.left) + assert_sizes(&node.right);
+                assert_eq!(node.size, size, "wrong size stored in node");
+                size
+            },
+        }
+    }
+
     /// Helper function to create tree of planet names
     fn create_planets() -> BinaryTree<String> {
         let planets = vec![
@@ -174,7 +633,7 @@ mod tests {
             "Neptune",
         ];
 
-        let planets = planets.into_iter().map(|e| String::from(e));
+        let planets = planets.into_iter().map(String::from);
         BinaryTree::from_iter(planets)
     }
 
@@ -197,6 +656,7 @@ mod tests {
                 element: "Jupiter",
                 left: BinaryTree::Empty,
                 right: BinaryTree::Empty,
+                size: 1,
             })
         );
 
@@ -242,6 +702,7 @@ mod tests {
                 element: "Jupiter",
                 left: BinaryTree::Empty,
                 right: BinaryTree::Empty,
+                size: 1,
             })
         );
 
@@ -250,6 +711,7 @@ mod tests {
                 element: "Mercury",
                 left: BinaryTree::Empty,
                 right: BinaryTree::Empty,
+                size: 1,
             })
         );
 
@@ -259,6 +721,7 @@ mod tests {
                 element: "Mars",
                 left: jupiter,
                 right: mercury,
+                size: 3,
             })
         );
         assert_eq!(format!("{mars:#?}"), SMALL_TREE_EXPECTED);
@@ -296,4 +759,254 @@ mod tests {
         ];
         assert_eq!(v, expected);
     }
+
+    #[test]
+    fn test_iter_rev() {
+        let tree = create_planets();
+        let v: Vec<_> = tree.iter().rev().take(3).collect();
+        assert_eq!(v, vec!["Venus", "Uranus", "Saturn"]);
+    }
+
+    #[test]
+    fn test_iter_both_ends() {
+        // Ends must meet in the middle without skipping or repeating
+        let tree = create_planets();
+        for split in 0..=tree.len() {
+            let mut iter = tree.iter();
+            let mut v: Vec<_> = iter.by_ref().take(split).collect();
+            let mut back: Vec<_> = iter.by_ref().rev().collect();
+            back.reverse();
+            v.extend(back);
+            assert_eq!(v, tree.iter().collect::<Vec<_>>());
+            assert_eq!(iter.next(), None);
+            assert_eq!(iter.next_back(), None);
+        }
+    }
+
+    #[test]
+    fn test_iter_alternating_ends() {
+        let tree: BinaryTree<u32> = [5, 3, 8, 1, 4, 7, 9, 2, 6].into_iter().collect();
+        let mut iter = tree.iter();
+        let mut v = Vec::new();
+        loop {
+            match (iter.next(), iter.next_back()) {
+                (Some(a), Some(b)) => v.extend([*a, *b]),
+                (Some(a), None) => v.push(*a),
+                (None, _) => break,
+            }
+        }
+        assert_eq!(v, vec![1, 9, 2, 8, 3, 7, 4, 6, 5]);
+    }
+
+    #[test]
+    fn test_iter_mut() {
+        let mut tree: BinaryTree<u32> = [2, 1, 3].into_iter().collect();
+        for value in tree.iter_mut() {
+            *value *= 10;
+        }
+        for value in &mut tree {
+            *value += 1;
+        }
+        assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![11, 21, 31]);
+    }
+
+    #[test]
+    fn test_iter_mut_rev() {
+        let mut tree: BinaryTree<u32> = [5, 3, 8, 1, 4, 7, 9, 2, 6].into_iter().collect();
+        for (i, value) in tree.iter_mut().rev().enumerate() {
+            *value += 10 * i as u32;
+        }
+        let v: Vec<_> = tree.iter().copied().collect();
+        assert_eq!(v, vec![81, 72, 63, 54, 45, 36, 27, 18, 9]);
+    }
+
+    #[test]
+    fn test_iter_mut_both_ends() {
+        let mut tree: BinaryTree<u32> = [5, 3, 8, 1, 4, 7, 9, 2, 6].into_iter().collect();
+        for split in 0..=tree.len() {
+            let mut iter = tree.iter_mut();
+            let mut v: Vec<u32> = iter.by_ref().take(split).map(|value| *value).collect();
+            let mut back: Vec<u32> = iter.by_ref().rev().map(|value| *value).collect();
+            back.reverse();
+            v.extend(back);
+            assert_eq!(v, (1..=9).collect::<Vec<_>>());
+            assert!(iter.next().is_none());
+            assert!(iter.next_back().is_none());
+        }
+    }
+
+    #[test]
+    fn test_into_iter() {
+        let tree = create_planets();
+        let v: Vec<String> = tree.into_iter().collect();
+        let expected = vec![
+            "Earth",
+            "Jupiter",
+            "Mars",
+            "Mercury",
+            "Neptune",
+            "Saturn",
+            "Uranus",
+            "Venus",
+        ];
+        assert_eq!(v, expected);
+    }
+
+    #[test]
+    fn test_into_iter_empty() {
+        let tree: BinaryTree<String> = BinaryTree::empty();
+        assert_eq!(tree.into_iter().next(), None);
+        let tree: BinaryTree<String> = BinaryTree::empty();
+        assert_eq!(tree.into_iter().next_back(), None);
+    }
+
+    #[test]
+    fn test_into_iter_rev() {
+        let v: Vec<String> = create_planets().into_iter().rev().take(3).collect();
+        assert_eq!(v, vec!["Venus", "Uranus", "Saturn"]);
+    }
+
+    #[test]
+    fn test_into_iter_alternating_ends() {
+        let tree: BinaryTree<u32> = [5, 3, 8, 1, 4, 7, 9, 2, 6].into_iter().collect();
+        let mut iter = tree.into_iter();
+        let mut v = Vec::new();
+        loop {
+            match (iter.next(), iter.next_back()) {
+                (Some(a), Some(b)) => v.extend([a, b]),
+                (Some(a), None) => v.push(a),
+                (None, _) => break,
+            }
+        }
+        assert_eq!(v, vec![1, 9, 2, 8, 3, 7, 4, 6, 5]);
+    }
+
+    #[test]
+    fn test_height() {
+        let empty: BinaryTree<i32> = BinaryTree::empty();
+        assert_eq!(empty.height(), 0);
+        assert_eq!(BinaryTree::new(1).height(), 1);
+
+        // Earth, Mars, Jupiter down the left of Mercury's sub-tree
+        let tree = create_planets();
+        assert_eq!(tree.height(), 4);
+    }
+
+    #[test]
+    fn test_is_balanced() {
+        let empty: BinaryTree<i32> = BinaryTree::empty();
+        assert!(empty.is_balanced());
+        assert!(BinaryTree::from_iter([2, 1, 3]).is_balanced());
+        assert!(!BinaryTree::from_iter([1, 2, 3]).is_balanced());
+        assert!(!create_planets().is_balanced());
+    }
+
+    #[test]
+    fn test_from_sorted_iter() {
+        for len in 0..100 {
+            let tree = BinaryTree::from_sorted_iter(0..len);
+            assert_eq!(tree.len(), len);
+            assert!(tree.is_balanced());
+            assert_eq!(tree.height(), (usize::BITS - len.leading_zeros()) as usize);
+            assert!(tree.iter().copied().eq(0..len));
+        }
+    }
+
+    #[test]
+    fn test_split_off() {
+        let mut low = create_planets();
+        let high = low.split_off(&String::from("Mercury"));
+        let low: Vec<_> = low.iter().collect();
+        let high: Vec<_> = high.iter().collect();
+        assert_eq!(low, vec!["Earth", "Jupiter", "Mars"]);
+        assert_eq!(high, vec!["Mercury", "Neptune", "Saturn", "Uranus", "Venus"]);
+    }
+
+    #[test]
+    fn test_split_off_every_key() {
+        for key in 0..=10 {
+            let mut low = BinaryTree::from_iter([5, 2, 8, 1, 3, 7, 9, 4, 6, 5]);
+            let high = low.split_off(&key);
+            assert!(low.iter().all(|e| *e < key));
+            assert!(high.iter().all(|e| *e >= key));
+            assert_eq!(low.len() + high.len(), 10);
+        }
+    }
+
+    #[test]
+    fn test_append() {
+        let mut a = BinaryTree::from_iter([1, 5, 3, 3]);
+        let mut b = BinaryTree::from_iter([4, 2, 6, 3]);
+        a.append(&mut b);
+        assert!(b.is_empty());
+        assert!(a.is_balanced());
+        assert_eq!(a.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3, 3, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_sizes() {
+        let mut tree = create_planets();
+        assert_eq!(assert_sizes(&tree), 8);
+
+        let mut high = tree.split_off(&String::from("Neptune"));
+        assert_eq!(assert_sizes(&tree), 4);
+        assert_eq!(assert_sizes(&high), 4);
+
+        tree.append(&mut high);
+        assert_eq!(assert_sizes(&tree), 8);
+        assert_eq!(tree.len(), 8);
+    }
+
+    #[test]
+    fn test_sizes_split_off_every_key() {
+        for key in 0..=10 {
+            let mut low = BinaryTree::from_iter([5, 2, 8, 1, 3, 7, 9, 4, 6, 5]);
+            let high = low.split_off(&key);
+            assert_eq!(assert_sizes(&low), low.iter().count());
+            assert_eq!(assert_sizes(&high), high.iter().count());
+        }
+    }
+
+    #[test]
+    fn test_select() {
+        let tree = create_planets();
+        assert_eq!(tree.select(0).unwrap(), "Earth");
+        assert_eq!(tree.select(3).unwrap(), "Mercury");
+        assert_eq!(tree.select(7).unwrap(), "Venus");
+        assert_eq!(tree.select(8), None);
+        for (k, planet) in tree.iter().enumerate() {
+            assert_eq!(tree.select(k), Some(planet));
+        }
+    }
+
+    #[test]
+    fn test_rank() {
+        let tree = create_planets();
+        assert_eq!(tree.rank(&String::from("Earth")), 0);
+        assert_eq!(tree.rank(&String::from("Mars")), 2);
+        assert_eq!(tree.rank(&String::from("Pluto")), 5);
+        assert_eq!(tree.rank(&String::from("Zeus")), 8);
+    }
+
+    #[test]
+    fn test_rank_duplicates() {
+        // Duplicates end up on both sides of a node once rebuilt
+        let tree = BinaryTree::from_sorted_iter([1, 2, 2, 2, 2, 3]);
+        assert_eq!(tree.rank(&2), 1);
+        assert_eq!(tree.rank(&3), 5);
+    }
+
+    #[test]
+    fn test_percentiles() {
+        // Streaming samples, queried as they arrive
+        let mut samples = BinaryTree::empty();
+        for sample in (0..1000).map(|i| (i * 7919) % 1000) {
+            samples.add(sample);
+        }
+        let percentile = |p: usize| *samples.select(p * (samples.len() - 1) / 100).unwrap();
+        assert_eq!(percentile(0), 0);
+        assert_eq!(percentile(50), 499);
+        assert_eq!(percentile(90), 899);
+        assert_eq!(percentile(100), 999);
+    }
 }
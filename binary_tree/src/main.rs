@@ -19,4 +19,8 @@ fn main() {
     for planet in &planets {
         println!("{planet}");
     }
+
+    println!();
+    println!("Sorted by inserting into this tree:");
+    print!("{}", planets.to_ascii_top_down());
 }
@@ -0,0 +1,669 @@
+//! Ordered key-value map built on top of `BinaryTree`.
+//!
+//! Nodes hold `(key, value)` tuples, but only the key takes part in
+//! ordering, so values need not implement `Ord` at all.
+
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::Index;
+
+use crate::{BinaryTree, TreeIter, TreeIterMut};
+
+
+/// A map from keys to values, ordered by key.
+pub struct BinaryTreeMap<K, V> {
+    root: BinaryTree<(K, V)>,
+}
+
+
+impl<K, V> BinaryTreeMap<K, V> {
+    /// Create new, empty map.
+    pub fn new() -> Self {
+        BinaryTreeMap { root: BinaryTree::Empty }
+    }
+
+    /// Build a perfectly balanced map from entries in ascending order of
+    /// key, none repeated.
+    #[cfg(feature = "serde")]
+    pub(crate) fn from_sorted_entries(entries: Vec<(K, V)>) -> Self {
+        BinaryTreeMap { root: BinaryTree::from_sorted_iter(entries) }
+    }
+
+    /// Does our map contain no entries?
+    pub fn is_empty(&self) -> bool {
+        self.root.is_empty()
+    }
+
+    /// How many entries does our map contain?
+    pub fn len(&self) -> usize {
+        self.root.len()
+    }
+
+    /// Iterator over `(&key, &value)` pairs, in key order.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter { inner: self.root.iter() }
+    }
+
+    /// Iterator over keys, in order.
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys { inner: self.iter() }
+    }
+
+    /// Iterator over values, in key order.
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values { inner: self.iter() }
+    }
+
+    /// Iterator over mutable references to values, in key order.
+    pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> {
+        ValuesMut { inner: self.root.iter_mut() }
+    }
+}
+
+
+impl<K: Ord, V> BinaryTreeMap<K, V> {
+    /// Insert value under the given key.
+    /// Returns the previous value if the key was already present.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.entry(key) {
+            Entry::Occupied(mut entry) => Some(entry.insert(value)),
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+                None
+            },
+        }
+    }
+
+    /// Shared reference to the value stored under key, if any.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut tree = &self.root;
+        while let BinaryTree::NonEmpty(ref node) = *tree {
+            tree = match key.cmp(node.element.0.borrow()) {
+                Ordering::Less => &node.left,
+                Ordering::Greater => &node.right,
+                Ordering::Equal => return Some(&node.element.1),
+            };
+        }
+        None
+    }
+
+    /// Mutable reference to the value stored under key, if any.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        match find_slot(&mut self.root, key, 0) {
+            BinaryTree::NonEmpty(node) => Some(&mut node.element.1),
+            BinaryTree::Empty => None,
+        }
+    }
+
+    /// Is there a value stored under key?
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Remove key from map, returning its value if it was present.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    /// Remove key from map, returning the stored key and value.
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        if !self.contains_key(key) {
+            return None;
+        }
+        Some(find_slot(&mut self.root, key, -1).remove_root())
+    }
+
+    /// Get the given key's entry for in-place manipulation.
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
+        let root = &mut self.root;
+        if find_slot(root, &key, 0).is_empty() {
+            Entry::Vacant(VacantEntry { key, root })
+        } else {
+            Entry::Occupied(OccupiedEntry { key, root })
+        }
+    }
+}
+
+
+/// Walk down from `tree` to the sub-tree whose root holds `key`, or to the
+/// empty sub-tree where `key` would be inserted. The size of every node
+/// passed on the way is adjusted by `size_change`.
+fn find_slot<'a, K, V, Q>(
+    mut tree: &'a mut BinaryTree<(K, V)>,
+    key: &Q,
+    size_change: isize,
+) -> &'a mut BinaryTree<(K, V)>
+where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    loop {
+        let ordering = match *tree {
+            BinaryTree::NonEmpty(ref node) => key.cmp(node.element.0.borrow()),
+            BinaryTree::Empty => return tree,
+        };
+        let node = match (ordering, tree) {
+            (Ordering::Equal, tree) => return tree,
+            (_, BinaryTree::NonEmpty(node)) => node,
+            (_, BinaryTree::Empty) => unreachable!(),
+        };
+        node.size = node.size.checked_add_signed(size_change).unwrap();
+        tree = match ordering {
+            Ordering::Less => &mut node.left,
+            _ => &mut node.right,
+        };
+    }
+}
+
+
+impl<K, V> Default for BinaryTreeMap<K, V> {
+    fn default() -> Self {
+        BinaryTreeMap::new()
+    }
+}
+
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for BinaryTreeMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+
+/// Implement FromIterator. Later values replace earlier ones with equal keys.
+impl<K: Ord, V> FromIterator<(K, V)> for BinaryTreeMap<K, V> {
+    fn from_iter<I: IntoIterator<Item=(K, V)>>(iter: I) -> Self {
+        let mut map = BinaryTreeMap::new();
+        for (key, value) in iter {
+            map.insert(key, value);
+        }
+        map
+    }
+}
+
+
+/// Panics if the key is not present in the map.
+impl<K, V, Q> Index<&Q> for BinaryTreeMap<K, V>
+where
+    K: Ord + Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    type Output = V;
+
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("key not found in BinaryTreeMap")
+    }
+}
+
+
+impl<'a, K, V> IntoIterator for &'a BinaryTreeMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+
+/// A view into a single entry of a map, which may be vacant or occupied.
+pub enum Entry<'a, K, V> {
+    Vacant(VacantEntry<'a, K, V>),
+    Occupied(OccupiedEntry<'a, K, V>),
+}
+
+
+/// A vacant entry holds the key and the map's root, so that sizes along
+/// the path to the new node can be updated on insertion.
+pub struct VacantEntry<'a, K, V> {
+    key: K,
+    root: &'a mut BinaryTree<(K, V)>,
+}
+
+
+/// An occupied entry holds the key and the map's root, so that sizes along
+/// the path to its node can be updated on removal.
+pub struct OccupiedEntry<'a, K, V> {
+    key: K,
+    root: &'a mut BinaryTree<(K, V)>,
+}
+
+
+impl<'a, K: Ord, V> Entry<'a, K, V> {
+    /// Reference to this entry's key.
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Vacant(entry) => entry.key(),
+            Entry::Occupied(entry) => entry.key(),
+        }
+    }
+
+    /// Insert default if vacant, then return mutable reference to value.
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            Entry::Vacant(entry) => entry.insert(default),
+            Entry::Occupied(entry) => entry.into_mut(),
+        }
+    }
+
+    /// Like `or_insert()`, but only calls `default` if the entry is vacant.
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Vacant(entry) => entry.insert(default()),
+            Entry::Occupied(entry) => entry.into_mut(),
+        }
+    }
+
+    /// Like `or_insert_with()`, but `default` is given the entry's key.
+    pub fn or_insert_with_key<F: FnOnce(&K) -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Vacant(entry) => {
+                let value = default(&entry.key);
+                entry.insert(value)
+            },
+            Entry::Occupied(entry) => entry.into_mut(),
+        }
+    }
+
+    /// Modify value in place if the entry is occupied.
+    pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+        match self {
+            Entry::Vacant(entry) => Entry::Vacant(entry),
+            Entry::Occupied(mut entry) => {
+                f(entry.get_mut());
+                Entry::Occupied(entry)
+            },
+        }
+    }
+}
+
+
+impl<'a, K: Ord, V: Default> Entry<'a, K, V> {
+    /// Insert `V::default()` if vacant, then return mutable reference to value.
+    pub fn or_default(self) -> &'a mut V {
+        self.or_insert_with(V::default)
+    }
+}
+
+
+impl<'a, K: Ord, V> VacantEntry<'a, K, V> {
+    /// Reference to the key that would be used on insertion.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Take ownership of the key back.
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    /// Insert value into the map, returning a mutable reference to it.
+    pub fn insert(self, value: V) -> &'a mut V {
+        let slot = find_slot(self.root, &self.key, 1);
+        *slot = BinaryTree::new((self.key, value));
+        match slot {
+            BinaryTree::NonEmpty(node) => &mut node.element.1,
+            BinaryTree::Empty => unreachable!(),
+        }
+    }
+}
+
+
+impl<'a, K: Ord, V> OccupiedEntry<'a, K, V> {
+    /// Reference to the key stored in the map.
+    pub fn key(&self) -> &K {
+        &self.element().0
+    }
+
+    /// Shared reference to the value.
+    pub fn get(&self) -> &V {
+        &self.element().1
+    }
+
+    /// Mutable reference to the value, borrowed from the entry.
+    pub fn get_mut(&mut self) -> &mut V {
+        match find_slot(self.root, &self.key, 0) {
+            BinaryTree::NonEmpty(node) => &mut node.element.1,
+            BinaryTree::Empty => unreachable!(),
+        }
+    }
+
+    /// Mutable reference to the value, for as long as the map is borrowed.
+    pub fn into_mut(self) -> &'a mut V {
+        match find_slot(self.root, &self.key, 0) {
+            BinaryTree::NonEmpty(node) => &mut node.element.1,
+            BinaryTree::Empty => unreachable!(),
+        }
+    }
+
+    /// Replace value, returning the old one.
+    pub fn insert(&mut self, value: V) -> V {
+        std::mem::replace(self.get_mut(), value)
+    }
+
+    /// Remove entry from the map, returning its value.
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    /// Remove entry from the map, returning the stored key and value.
+    pub fn remove_entry(self) -> (K, V) {
+        find_slot(self.root, &self.key, -1).remove_root()
+    }
+
+    /// The key and value stored in the map.
+    fn element(&self) -> &(K, V) {
+        let mut tree = &*self.root;
+        while let BinaryTree::NonEmpty(ref node) = *tree {
+            tree = match self.key.cmp(&node.element.0) {
+                Ordering::Less => &node.left,
+                Ordering::Greater => &node.right,
+                Ordering::Equal => return &node.element,
+            };
+        }
+        unreachable!()
+    }
+}
+
+
+/// Iterator over `(&key, &value)` pairs of a `BinaryTreeMap`.
+#[derive(Debug)]
+pub struct Iter<'a, K, V> {
+    inner: TreeIter<'a, (K, V)>,
+}
+
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(key, value)| (key, value))
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Iter<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(key, value)| (key, value))
+    }
+}
+
+
+/// Iterator over the keys of a `BinaryTreeMap`.
+#[derive(Debug)]
+pub struct Keys<'a, K, V> {
+    inner: Iter<'a, K, V>,
+}
+
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        self.inner.next().map(|(key, _)| key)
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Keys<'a, K, V> {
+    fn next_back(&mut self) -> Option<&'a K> {
+        self.inner.next_back().map(|(key, _)| key)
+    }
+}
+
+
+/// Iterator over the values of a `BinaryTreeMap`.
+#[derive(Debug)]
+pub struct Values<'a, K, V> {
+    inner: Iter<'a, K, V>,
+}
+
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<&'a V> {
+        self.inner.next().map(|(_, value)| value)
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Values<'a, K, V> {
+    fn next_back(&mut self) -> Option<&'a V> {
+        self.inner.next_back().map(|(_, value)| value)
+    }
+}
+
+
+/// Iterator over mutable references to the values of a `BinaryTreeMap`.
+/// Keys stay shared so that the map's ordering can't be broken.
+#[derive(Debug)]
+pub struct ValuesMut<'a, K, V> {
+    inner: TreeIterMut<'a, (K, V)>,
+}
+
+
+impl<'a, K, V> Iterator for ValuesMut<'a, K, V> {
+    type Item = &'a mut V;
+
+    fn next(&mut self) -> Option<&'a mut V> {
+        self.inner.next().map(|(_, value)| value)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::assert_sizes;
+    use pretty_assertions::assert_eq;
+
+    /// Helper function to create map of planet names to their moon counts
+    fn create_moons() -> BinaryTreeMap<&'static str, u32> {
+        [
+            ("Mercury", 0),
+            ("Venus", 0),
+            ("Earth", 1),
+            ("Mars", 2),
+            ("Jupiter", 95),
+            ("Saturn", 146),
+            ("Uranus", 28),
+            ("Neptune", 16),
+        ].into_iter().collect()
+    }
+
+    #[test]
+    fn test_new() {
+        let map: BinaryTreeMap<String, i32> = BinaryTreeMap::new();
+        assert!(map.is_empty());
+        assert_eq!(map.len(), 0);
+        assert_eq!(format!("{map:?}"), "{}");
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut map = BinaryTreeMap::new();
+        assert_eq!(map.insert("Mars", 2), None);
+        assert_eq!(map.insert("Earth", 1), None);
+        assert_eq!(map.insert("Mars", 3), Some(2));
+        assert_eq!(map.len(), 2);
+        assert_eq!(format!("{map:?}"), r#"{"Earth": 1, "Mars": 3}"#);
+    }
+
+    #[test]
+    fn test_get() {
+        let map = create_moons();
+        assert_eq!(map.get("Saturn"), Some(&146));
+        assert_eq!(map.get("Pluto"), None);
+        assert!(map.contains_key("Earth"));
+        assert!(!map.contains_key("Vulcan"));
+    }
+
+    #[test]
+    fn test_get_borrowed_key() {
+        // Look up `String` keys using a `&str`
+        let mut map = BinaryTreeMap::new();
+        map.insert(String::from("Earth"), 1);
+        assert_eq!(map.get("Earth"), Some(&1));
+        assert_eq!(map["Earth"], 1);
+    }
+
+    #[test]
+    fn test_get_mut() {
+        let mut map = create_moons();
+        *map.get_mut("Earth").unwrap() += 1;
+        assert_eq!(map["Earth"], 2);
+        assert_eq!(map.get_mut("Pluto"), None);
+    }
+
+    #[test]
+    fn test_index() {
+        let map = create_moons();
+        assert_eq!(map["Jupiter"], 95);
+    }
+
+    #[test]
+    #[should_panic(expected = "key not found")]
+    fn test_index_missing() {
+        let map = create_moons();
+        let _ = map["Pluto"];
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut map = create_moons();
+        let mut keys: Vec<_> = map.keys().copied().collect();
+
+        // Remove root, leaves, and nodes with one or two children alike
+        while let Some(key) = keys.pop() {
+            assert!(map.remove(key).is_some());
+            assert_eq!(map.remove(key), None);
+            assert_eq!(map.len(), keys.len());
+            assert_eq!(assert_sizes(&map.root), keys.len());
+            assert_eq!(map.keys().copied().collect::<Vec<_>>(), keys);
+        }
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_remove_node_with_two_children() {
+        let mut map = create_moons();
+        assert_eq!(map.remove_entry("Mercury"), Some(("Mercury", 0)));
+        let keys: Vec<_> = map.keys().copied().collect();
+        let expected = vec![
+            "Earth", "Jupiter", "Mars", "Neptune", "Saturn", "Uranus", "Venus",
+        ];
+        assert_eq!(keys, expected);
+    }
+
+    #[test]
+    fn test_entry_counting() {
+        let text = "the quick brown fox jumps over the lazy dog the end";
+        let mut counts = BinaryTreeMap::new();
+        for word in text.split_whitespace() {
+            *counts.entry(word).or_insert(0) += 1;
+        }
+        assert_eq!(counts["the"], 3);
+        assert_eq!(counts["fox"], 1);
+        assert_eq!(counts.len(), 9);
+        assert_eq!(assert_sizes(&counts.root), 9);
+    }
+
+    #[test]
+    fn test_entry_variants() {
+        let mut map = create_moons();
+
+        match map.entry("Earth") {
+            Entry::Occupied(mut entry) => {
+                assert_eq!(entry.key(), &"Earth");
+                assert_eq!(entry.insert(7), 1);
+                assert_eq!(entry.get(), &7);
+            },
+            Entry::Vacant(_) => panic!("Earth should be present"),
+        }
+
+        match map.entry("Pluto") {
+            Entry::Vacant(entry) => {
+                assert_eq!(entry.key(), &"Pluto");
+                *entry.insert(4) += 1;
+            },
+            Entry::Occupied(_) => panic!("Pluto should be absent"),
+        }
+        assert_eq!(map["Pluto"], 5);
+
+        match map.entry("Pluto") {
+            Entry::Occupied(entry) => assert_eq!(entry.remove(), 5),
+            Entry::Vacant(_) => panic!("Pluto should be present"),
+        }
+        assert!(!map.contains_key("Pluto"));
+        assert_eq!(assert_sizes(&map.root), 8);
+    }
+
+    #[test]
+    fn test_entry_and_modify() {
+        let mut map = create_moons();
+        map.entry("Mars").and_modify(|v| *v *= 10).or_insert(0);
+        map.entry("Ceres").and_modify(|v| *v *= 10).or_default();
+        map.entry("Eris").or_insert_with_key(|key| key.len() as u32);
+        assert_eq!(map["Mars"], 20);
+        assert_eq!(map["Ceres"], 0);
+        assert_eq!(map["Eris"], 4);
+    }
+
+    #[test]
+    fn test_iter() {
+        let map = create_moons();
+        let pairs: Vec<_> = map.iter().take(3).collect();
+        assert_eq!(pairs, vec![(&"Earth", &1), (&"Jupiter", &95), (&"Mars", &2)]);
+    }
+
+    #[test]
+    fn test_keys_and_values() {
+        let map = create_moons();
+        let keys: Vec<_> = map.keys().copied().collect();
+        let values: Vec<_> = map.values().copied().collect();
+        assert_eq!(keys, vec![
+            "Earth", "Jupiter", "Mars", "Mercury",
+            "Neptune", "Saturn", "Uranus", "Venus",
+        ]);
+        assert_eq!(values, vec![1, 95, 2, 0, 16, 146, 28, 0]);
+    }
+
+    #[test]
+    fn test_values_mut() {
+        let mut map = create_moons();
+        for value in map.values_mut() {
+            *value *= 2;
+        }
+        let values: Vec<_> = map.values().copied().collect();
+        assert_eq!(values, vec![2, 190, 4, 0, 32, 292, 56, 0]);
+    }
+
+    #[test]
+    fn test_values_need_not_be_ord() {
+        // Floats are only `PartialOrd`, which is fine for values
+        let mut map = BinaryTreeMap::new();
+        map.insert(2, 0.5_f64);
+        map.insert(1, f64::NAN);
+        assert_eq!(map.len(), 2);
+        assert!(map[&1].is_nan());
+    }
+}
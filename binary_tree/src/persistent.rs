@@ -0,0 +1,326 @@
+//! Persistent (immutable) binary tree.
+//!
+//! Nodes are reference-counted and never modified once built. Changing a
+//! tree copies only the nodes on the path from the root to the change, and
+//! the new tree shares every other sub-tree with the old one. Keeping many
+//! versions of a large tree around is therefore cheap, and cloning one is
+//! O(1).
+
+use std::rc::Rc;
+
+use crate::BinaryTree;
+
+
+/// `PersistentTree`s are either empty or contain a shared `PersistentNode`
+#[derive(Debug)]
+pub enum PersistentTree<T> {
+    Empty,
+    NonEmpty(Rc<PersistentNode<T>>),
+}
+
+
+/// Every `PersistentNode` contains data, and possibly child trees, which
+/// may be shared with other versions of the tree.
+#[derive(Debug)]
+pub struct PersistentNode<T> {
+    element: T,
+    left: PersistentTree<T>,
+    right: PersistentTree<T>,
+}
+
+
+impl<T> PersistentTree<T> {
+    /// Create new, empty tree.
+    pub fn empty() -> Self {
+        PersistentTree::Empty
+    }
+
+    /// Is our tree empty?
+    pub fn is_empty(&self) -> bool {
+        matches!(self, PersistentTree::Empty)
+    }
+
+    /// Create iterator over a shared reference
+    pub fn iter(&self) -> PersistentIter<'_, T> {
+        let mut iter = PersistentIter { unvisited: Vec::new() };
+        iter.push_left_edge(self);
+        iter
+    }
+
+    /// How many nodes does our tree contain?
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Build a new node from its parts.
+    fn node(element: T, left: Self, right: Self) -> Self {
+        PersistentTree::NonEmpty(Rc::new(PersistentNode { element, left, right }))
+    }
+}
+
+
+impl<T: Clone + Ord> PersistentTree<T> {
+    /// New version of this tree with value added.
+    /// Only nodes on the path to the new leaf are copied.
+    pub fn insert(&self, value: T) -> Self {
+        match *self {
+            PersistentTree::Empty => {
+                PersistentTree::node(value, PersistentTree::Empty, PersistentTree::Empty)
+            },
+            PersistentTree::NonEmpty(ref node) => {
+                if value <= node.element {
+                    PersistentTree::node(
+                        node.element.clone(),
+                        node.left.insert(value),
+                        node.right.clone(),
+                    )
+                } else {
+                    PersistentTree::node(
+                        node.element.clone(),
+                        node.left.clone(),
+                        node.right.insert(value),
+                    )
+                }
+            },
+        }
+    }
+
+    /// New version of this tree with one copy of value removed.
+    /// If value is not present, the new tree shares all of this one.
+    pub fn remove(&self, value: &T) -> Self {
+        match *self {
+            PersistentTree::Empty => PersistentTree::Empty,
+            PersistentTree::NonEmpty(ref node) => {
+                if *value < node.element {
+                    let left = node.left.remove(value);
+                    if left.ptr_eq(&node.left) {
+                        return self.clone();
+                    }
+                    PersistentTree::node(node.element.clone(), left, node.right.clone())
+                } else if *value > node.element {
+                    let right = node.right.remove(value);
+                    if right.ptr_eq(&node.right) {
+                        return self.clone();
+                    }
+                    PersistentTree::node(node.element.clone(), node.left.clone(), right)
+                } else {
+                    match (&node.left, &node.right) {
+                        (PersistentTree::Empty, right) => right.clone(),
+                        (left, PersistentTree::Empty) => left.clone(),
+                        (left, right) => {
+                            let (successor, right) = right.remove_first();
+                            PersistentTree::node(successor, left.clone(), right)
+                        },
+                    }
+                }
+            },
+        }
+    }
+
+    /// Does our tree contain value?
+    pub fn contains(&self, value: &T) -> bool {
+        let mut tree = self;
+        while let PersistentTree::NonEmpty(ref node) = *tree {
+            if *value < node.element {
+                tree = &node.left;
+            } else if *value > node.element {
+                tree = &node.right;
+            } else {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// New version of this tree without its left-most node, along with a
+    /// copy of that node's element.
+    ///
+    /// Panics if the tree is empty.
+    fn remove_first(&self) -> (T, Self) {
+        match *self {
+            PersistentTree::Empty => panic!("remove_first() called on empty tree"),
+            PersistentTree::NonEmpty(ref node) => {
+                if node.left.is_empty() {
+                    (node.element.clone(), node.right.clone())
+                } else {
+                    let (first, left) = node.left.remove_first();
+                    let tree = PersistentTree::node(
+                        node.element.clone(),
+                        left,
+                        node.right.clone(),
+                    );
+                    (first, tree)
+                }
+            },
+        }
+    }
+}
+
+
+impl<T> PersistentTree<T> {
+    /// Are both trees the very same version, sharing a root node?
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (PersistentTree::Empty, PersistentTree::Empty) => true,
+            (PersistentTree::NonEmpty(a), PersistentTree::NonEmpty(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+
+/// Cloning only bumps the root's reference count.
+impl<T> Clone for PersistentTree<T> {
+    fn clone(&self) -> Self {
+        match self {
+            PersistentTree::Empty => PersistentTree::Empty,
+            PersistentTree::NonEmpty(node) => PersistentTree::NonEmpty(Rc::clone(node)),
+        }
+    }
+}
+
+
+/// Implement FromIterator
+impl<T: Clone + Ord> FromIterator<T> for PersistentTree<T> {
+    fn from_iter<I: IntoIterator<Item=T>>(iter: I) -> Self {
+        let mut tree = PersistentTree::empty();
+        for item in iter {
+            tree = tree.insert(item);
+        }
+        tree
+    }
+}
+
+
+/// Snapshot a mutable `BinaryTree`, keeping its shape.
+impl<T: Clone> From<&BinaryTree<T>> for PersistentTree<T> {
+    fn from(tree: &BinaryTree<T>) -> Self {
+        match tree {
+            BinaryTree::Empty => PersistentTree::Empty,
+            BinaryTree::NonEmpty(node) => PersistentTree::node(
+                node.element.clone(),
+                PersistentTree::from(&node.left),
+                PersistentTree::from(&node.right),
+            ),
+        }
+    }
+}
+
+
+/// Implement the `IntoIterator` trait for `PersistentTree`
+impl<'a, T: 'a> IntoIterator for &'a PersistentTree<T> {
+    type Item = &'a T;
+    type IntoIter = PersistentIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+
+/// In-order iterator over `PersistentTree`.
+#[derive(Debug)]
+pub struct PersistentIter<'a, T> {
+    // Stack of references to tree nodes.
+    unvisited: Vec<&'a PersistentNode<T>>,
+}
+
+
+impl<'a, T: 'a> PersistentIter<'a, T> {
+    /// Walk the left edge of the tree, pushing every node seen onto the stack.
+    fn push_left_edge(&mut self, mut tree: &'a PersistentTree<T>) {
+        while let PersistentTree::NonEmpty(ref node) = *tree {
+            self.unvisited.push(node);
+            tree = &node.left;
+        }
+    }
+}
+
+impl<'a, T> Iterator for PersistentIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let node = self.unvisited.pop()?;
+        self.push_left_edge(&node.right);
+        Some(&node.element)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn to_vec(tree: &PersistentTree<u32>) -> Vec<u32> {
+        tree.iter().copied().collect()
+    }
+
+    #[test]
+    fn test_insert_keeps_old_version() {
+        let v1: PersistentTree<u32> = [4, 2, 6].into_iter().collect();
+        let v2 = v1.insert(5);
+        assert_eq!(to_vec(&v1), vec![2, 4, 6]);
+        assert_eq!(to_vec(&v2), vec![2, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_insert_shares_subtrees() {
+        let v1: PersistentTree<u32> = [4, 2, 6].into_iter().collect();
+        let v2 = v1.insert(5);
+        let (PersistentTree::NonEmpty(a), PersistentTree::NonEmpty(b)) = (&v1, &v2) else {
+            panic!("trees should not be empty");
+        };
+
+        // Root was copied, untouched left sub-tree was not
+        assert!(!Rc::ptr_eq(a, b));
+        assert!(a.left.ptr_eq(&b.left));
+        assert!(!a.right.ptr_eq(&b.right));
+    }
+
+    #[test]
+    fn test_remove() {
+        let v1: PersistentTree<u32> = [4, 2, 6, 1, 3, 5, 7].into_iter().collect();
+        let v2 = v1.remove(&4);
+        let v3 = v2.remove(&1);
+        assert_eq!(to_vec(&v1), vec![1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(to_vec(&v2), vec![1, 2, 3, 5, 6, 7]);
+        assert_eq!(to_vec(&v3), vec![2, 3, 5, 6, 7]);
+        assert!(!v3.contains(&1));
+        assert!(v1.contains(&1));
+    }
+
+    #[test]
+    fn test_remove_missing_shares_everything() {
+        let v1: PersistentTree<u32> = [4, 2, 6].into_iter().collect();
+        let v2 = v1.remove(&5);
+        assert!(v1.ptr_eq(&v2));
+    }
+
+    #[test]
+    fn test_clone_is_shallow() {
+        let v1: PersistentTree<u32> = [4, 2, 6].into_iter().collect();
+        let v2 = v1.clone();
+        assert!(v1.ptr_eq(&v2));
+        assert_eq!(v2.len(), 3);
+    }
+
+    #[test]
+    fn test_undo_history() {
+        let mut history = vec![PersistentTree::empty()];
+        for value in [5, 3, 8, 1] {
+            let next = history.last().unwrap().insert(value);
+            history.push(next);
+        }
+        let lengths: Vec<_> = history.iter().map(|tree| tree.len()).collect();
+        assert_eq!(lengths, vec![0, 1, 2, 3, 4]);
+        assert_eq!(to_vec(&history[2]), vec![3, 5]);
+    }
+
+    #[test]
+    fn test_from_binary_tree() {
+        let tree = BinaryTree::from_iter([4, 2, 6, 1, 3]);
+        let snapshot = PersistentTree::from(&tree);
+        assert_eq!(to_vec(&snapshot), vec![1, 2, 3, 4, 6]);
+    }
+}
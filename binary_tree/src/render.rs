@@ -0,0 +1,363 @@
+//! Render the shape of a `BinaryTree`, as Graphviz DOT source or as text
+//! drawn with box characters.
+
+use std::fmt::{Display, Write};
+
+use crate::{BinaryTree, TreeNode};
+
+
+impl<T: Display> BinaryTree<T> {
+    /// Graphviz DOT source for this tree, eg. for `dot -Tsvg`.
+    ///
+    /// Nodes with a single child are given an invisible sibling, so that
+    /// left and right children are drawn on the correct side.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph BinaryTree {\n");
+        let mut next_id = 0;
+        let mut unvisited: Vec<(&TreeNode<T>, usize)> = Vec::new();
+        if let BinaryTree::NonEmpty(ref node) = *self {
+            unvisited.push((node, next_id));
+            next_id += 1;
+        }
+
+        while let Some((node, id)) = unvisited.pop() {
+            let label = escape_dot(&node.element.to_string());
+            writeln!(dot, "    n{id} [label=\"{label}\"];").unwrap();
+            if node.left.is_empty() && node.right.is_empty() {
+                continue;
+            }
+
+            let mut children = Vec::new();
+            for child in [&node.left, &node.right] {
+                let child_id = next_id;
+                next_id += 1;
+                match child {
+                    BinaryTree::NonEmpty(child) => {
+                        writeln!(dot, "    n{id} -> n{child_id};").unwrap();
+                        children.push((&**child, child_id));
+                    },
+                    BinaryTree::Empty => {
+                        writeln!(dot, "    n{child_id} [style=invis];").unwrap();
+                        writeln!(dot, "    n{id} -> n{child_id} [style=invis];").unwrap();
+                    },
+                }
+            }
+
+            // Right pushed first so that left sub-tree is written first
+            unvisited.extend(children.into_iter().rev());
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Draw tree on its side, with the root on the left and right
+    /// sub-trees above left sub-trees.
+    ///
+    /// ```text
+    /// ┌── 6
+    /// │   └── 5
+    /// 4
+    /// └── 2
+    /// ```
+    pub fn to_ascii_sideways(&self) -> String {
+        // Frames are visited in reverse in-order: right, node, then left
+        enum Frame<'a, T> {
+            Visit(&'a BinaryTree<T>, String, Side),
+            Draw(&'a T, String, Side),
+        }
+
+        let mut text = String::new();
+        let mut unvisited = vec![Frame::Visit(self, String::new(), Side::Root)];
+        while let Some(frame) = unvisited.pop() {
+            match frame {
+                Frame::Visit(BinaryTree::Empty, _, _) => {},
+                Frame::Visit(BinaryTree::NonEmpty(node), prefix, side) => {
+                    let (above, below) = match side {
+                        Side::Root => ("", ""),
+                        Side::Right => ("    ", "│   "),
+                        Side::Left => ("│   ", "    "),
+                    };
+                    let above = format!("{prefix}{above}");
+                    let below = format!("{prefix}{below}");
+                    unvisited.push(Frame::Visit(&node.left, below, Side::Left));
+                    unvisited.push(Frame::Draw(&node.element, prefix, side));
+                    unvisited.push(Frame::Visit(&node.right, above, Side::Right));
+                },
+                Frame::Draw(element, prefix, side) => {
+                    let connector = match side {
+                        Side::Root => "",
+                        Side::Right => "┌── ",
+                        Side::Left => "└── ",
+                    };
+                    writeln!(text, "{prefix}{connector}{element}").unwrap();
+                },
+            }
+        }
+        text
+    }
+
+    /// Draw tree from the top down, with each node in its own column.
+    ///
+    /// ```text
+    ///       4
+    ///   ┌───┴───┐
+    ///   2       6
+    /// ┌─┴─┐   ┌─┘
+    /// 1   3   5
+    /// ```
+    pub fn to_ascii_top_down(&self) -> String {
+        let cells = layout(self);
+        let width = cells.iter().map(|cell| cell.label.chars().count()).max();
+        let Some(width) = width else {
+            return String::new();
+        };
+
+        // Every node gets a column wide enough for the longest label, and
+        // room for a connector even if every label is empty
+        let width = width.max(1);
+        let slot = width + 1;
+        let centre = |cell: &Cell| cell.column * slot + (width - 1) / 2;
+        let depth = cells.iter().map(|cell| cell.depth).max().unwrap_or(0);
+        let mut lines = vec![vec![' '; cells.len() * slot]; depth * 2 + 1];
+
+        for cell in &cells {
+            let length = cell.label.chars().count();
+            let start = centre(cell) - length.saturating_sub(1) / 2;
+            let line = &mut lines[cell.depth * 2];
+            for (i, c) in cell.label.chars().enumerate() {
+                line[start + i] = c;
+            }
+
+            // Connect node to its children on the line below
+            let here = centre(cell);
+            let left = cell.left.map(|i| centre(&cells[i]));
+            let right = cell.right.map(|i| centre(&cells[i]));
+            if left.is_none() && right.is_none() {
+                continue;
+            }
+            let line = &mut lines[cell.depth * 2 + 1];
+            let from = left.unwrap_or(here);
+            let to = right.unwrap_or(here);
+            for c in &mut line[from..=to] {
+                *c = '─';
+            }
+            line[here] = match (left, right) {
+                (Some(_), Some(_)) => '┴',
+                (Some(_), None) => '┘',
+                (None, _) => '└',
+            };
+            if left.is_some() {
+                line[from] = '┌';
+            }
+            if right.is_some() {
+                line[to] = '┐';
+            }
+        }
+
+        let mut text = String::new();
+        for line in lines {
+            let line: String = line.into_iter().collect();
+            text.push_str(line.trim_end());
+            text.push('\n');
+        }
+        text
+    }
+}
+
+
+/// Which side of its parent a node hangs from.
+#[derive(Clone, Copy)]
+enum Side {
+    Root,
+    Left,
+    Right,
+}
+
+
+/// Position of a single node when drawn from the top down.
+struct Cell {
+    label: String,
+    depth: usize,
+    // In-order position, which is also its column.
+    column: usize,
+    // Indices of child cells.
+    left: Option<usize>,
+    right: Option<usize>,
+}
+
+
+/// Position every node of the tree. Cells are created as nodes are pushed
+/// onto the in-order stack, and given their column as they are popped.
+fn layout<T: Display>(tree: &BinaryTree<T>) -> Vec<Cell> {
+    let mut cells = Vec::new();
+    let mut unvisited = Vec::new();
+    push_left_edge(&mut cells, &mut unvisited, tree, None, 0);
+
+    let mut column = 0;
+    while let Some((node, index)) = unvisited.pop() {
+        cells[index].column = column;
+        column += 1;
+        let depth = cells[index].depth + 1;
+        push_left_edge(&mut cells, &mut unvisited, &node.right, Some(index), depth);
+    }
+    cells
+}
+
+
+/// Walk the left edge of a sub-tree, creating a cell for every node seen.
+/// The top node of the edge becomes the right child of `parent`.
+fn push_left_edge<'a, T: Display>(
+    cells: &mut Vec<Cell>,
+    unvisited: &mut Vec<(&'a TreeNode<T>, usize)>,
+    mut tree: &'a BinaryTree<T>,
+    mut parent: Option<usize>,
+    mut depth: usize,
+) {
+    let mut is_left = false;
+    while let BinaryTree::NonEmpty(ref node) = *tree {
+        let index = cells.len();
+        cells.push(Cell {
+            label: node.element.to_string(),
+            depth,
+            column: 0,
+            left: None,
+            right: None,
+        });
+        if let Some(parent) = parent {
+            if is_left {
+                cells[parent].left = Some(index);
+            } else {
+                cells[parent].right = Some(index);
+            }
+        }
+        unvisited.push((node, index));
+        parent = Some(index);
+        is_left = true;
+        depth += 1;
+        tree = &node.left;
+    }
+}
+
+
+/// Escape text for use inside a double-quoted DOT string.
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    fn create_numbers() -> BinaryTree<u32> {
+        BinaryTree::from_iter([4, 2, 6, 1, 3, 5, 7])
+    }
+
+    #[test]
+    fn test_empty() {
+        let tree: BinaryTree<u32> = BinaryTree::empty();
+        assert_eq!(tree.to_dot(), "digraph BinaryTree {\n}\n");
+        assert_eq!(tree.to_ascii_sideways(), "");
+        assert_eq!(tree.to_ascii_top_down(), "");
+    }
+
+    #[test]
+    fn test_single_node() {
+        let tree = BinaryTree::new("Jupiter");
+        assert_eq!(tree.to_ascii_sideways(), "Jupiter\n");
+        assert_eq!(tree.to_ascii_top_down(), "Jupiter\n");
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let tree = BinaryTree::from_iter([2, 1, 3, 4]);
+        let expected = indoc! {r#"
+            digraph BinaryTree {
+                n0 [label="2"];
+                n0 -> n1;
+                n0 -> n2;
+                n1 [label="1"];
+                n2 [label="3"];
+                n3 [style=invis];
+                n2 -> n3 [style=invis];
+                n2 -> n4;
+                n4 [label="4"];
+            }
+        "#};
+        assert_eq!(tree.to_dot(), expected);
+    }
+
+    #[test]
+    fn test_to_dot_escaping() {
+        let tree = BinaryTree::from_iter([r#"say "hi"\"#]);
+        assert!(tree.to_dot().contains(r#"[label="say \"hi\"\\"]"#));
+    }
+
+    #[test]
+    fn test_sideways() {
+        let tree = create_numbers();
+        let expected = indoc! {"
+                ┌── 7
+            ┌── 6
+            │   └── 5
+            4
+            │   ┌── 3
+            └── 2
+                └── 1
+        "};
+        assert_eq!(tree.to_ascii_sideways(), expected);
+    }
+
+    #[test]
+    fn test_sideways_unbalanced() {
+        let tree = BinaryTree::from_iter([1, 3, 2, 4]);
+        let expected = indoc! {"
+                ┌── 4
+            ┌── 3
+            │   └── 2
+            1
+        "};
+        assert_eq!(tree.to_ascii_sideways(), expected);
+    }
+
+    #[test]
+    fn test_top_down() {
+        let tree = create_numbers();
+        let expected = indoc! {"
+                  4
+              ┌───┴───┐
+              2       6
+            ┌─┴─┐   ┌─┴─┐
+            1   3   5   7
+        "};
+        assert_eq!(tree.to_ascii_top_down(), expected);
+    }
+
+    #[test]
+    fn test_top_down_unbalanced() {
+        let tree = BinaryTree::from_iter([10, 30, 20, 5]);
+        let expected = indoc! {"
+               10
+            ┌──┴─────┐
+            5        30
+                  ┌──┘
+                  20
+        "};
+        assert_eq!(tree.to_ascii_top_down(), expected);
+    }
+
+    #[test]
+    fn test_top_down_empty_label() {
+        let tree = BinaryTree::from_iter(["a", "", "b"].map(String::from));
+        let expected = indoc! {"
+              a
+            ┌─┴─┐
+                b
+        "};
+        assert_eq!(tree.to_ascii_top_down(), expected);
+        assert_eq!(BinaryTree::new(String::new()).to_ascii_top_down(), "\n");
+    }
+}
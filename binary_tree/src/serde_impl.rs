@@ -0,0 +1,149 @@
+//! Serde support, enabled with the `serde` cargo feature.
+//!
+//! A `BinaryTree` is serialized as a sorted sequence of its elements, and a
+//! `BinaryTreeMap` as a map in key order. The shape of the tree is not kept:
+//! input is sorted and rebuilt into a perfectly balanced tree, so no input,
+//! however ordered, can build a degenerate one.
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+use crate::{BinaryTree, BinaryTreeMap};
+
+
+impl<T: Serialize> Serialize for BinaryTree<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+
+impl<'de, T: Deserialize<'de> + Ord> Deserialize<'de> for BinaryTree<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Repeated elements are kept, as `add()` would
+        let mut elements = Vec::<T>::deserialize(deserializer)?;
+        elements.sort();
+        Ok(BinaryTree::from_sorted_iter(elements))
+    }
+}
+
+
+impl<K: Serialize, V: Serialize> Serialize for BinaryTreeMap<K, V> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+
+impl<'de, K, V> Deserialize<'de> for BinaryTreeMap<K, V>
+where
+    K: Deserialize<'de> + Ord,
+    V: Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(MapVisitor(PhantomData))
+    }
+}
+
+
+/// Visitor building a `BinaryTreeMap` from all its entries at once.
+struct MapVisitor<K, V>(PhantomData<BinaryTreeMap<K, V>>);
+
+
+impl<'de, K, V> Visitor<'de> for MapVisitor<K, V>
+where
+    K: Deserialize<'de> + Ord,
+    V: Deserialize<'de>,
+{
+    type Value = BinaryTreeMap<K, V>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a map")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+        let mut entries = Vec::new();
+        while let Some(entry) = access.next_entry()? {
+            entries.push(entry);
+        }
+
+        // Later values replace earlier ones with equal keys, as for `insert()`
+        entries.sort_by(|a: &(K, V), b| a.0.cmp(&b.0));
+        entries.dedup_by(|later, earlier| {
+            let repeated = later.0 == earlier.0;
+            if repeated {
+                std::mem::swap(&mut later.1, &mut earlier.1);
+            }
+            repeated
+        });
+        Ok(BinaryTreeMap::from_sorted_entries(entries))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_serialize_tree() {
+        let tree = BinaryTree::from_iter([3, 1, 4, 1, 5, 9, 2, 6]);
+        let json = serde_json::to_string(&tree).unwrap();
+        assert_eq!(json, "[1,1,2,3,4,5,6,9]");
+    }
+
+    #[test]
+    fn test_deserialize_sorted_is_balanced() {
+        let tree: BinaryTree<u32> = serde_json::from_str("[1,2,3,4,5,6,7]").unwrap();
+        assert!(tree.is_balanced());
+        assert_eq!(tree.height(), 3);
+    }
+
+    #[test]
+    fn test_deserialize_unsorted() {
+        let tree: BinaryTree<u32> = serde_json::from_str("[3,1,2]").unwrap();
+        assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_deserialize_nearly_sorted_is_balanced() {
+        // Inserted one at a time, this would be a chain a million long
+        let mut elements: Vec<u32> = (0..1_000_000).collect();
+        elements.swap(0, 1);
+        let json = serde_json::to_string(&elements).unwrap();
+        let tree: BinaryTree<u32> = serde_json::from_str(&json).unwrap();
+        assert!(tree.is_balanced());
+        assert_eq!(tree.height(), 20);
+        assert_eq!(tree.len(), 1_000_000);
+
+        let tree: BinaryTree<u32> = serde_json::from_str("[2,1,2,1]").unwrap();
+        assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![1, 1, 2, 2]);
+    }
+
+    #[test]
+    fn test_deserialize_map_is_balanced() {
+        let json = r#"{"c":1,"a":2,"b":3,"d":4,"a":5}"#;
+        let map: BinaryTreeMap<String, u32> = serde_json::from_str(json).unwrap();
+        assert_eq!(map.len(), 4);
+        assert_eq!(map["a"], 5);
+        assert_eq!(map.keys().cloned().collect::<String>(), "abcd");
+    }
+
+    #[test]
+    fn test_round_trip_map() {
+        let map: BinaryTreeMap<String, u32> = [
+            (String::from("Mars"), 2),
+            (String::from("Earth"), 1),
+        ].into_iter().collect();
+        let json = serde_json::to_string(&map).unwrap();
+        assert_eq!(json, r#"{"Earth":1,"Mars":2}"#);
+
+        let map: BinaryTreeMap<String, u32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(map["Mars"], 2);
+        assert_eq!(map.len(), 2);
+    }
+}
@@ -0,0 +1,227 @@
+//! Set algebra between two `BinaryTree`s.
+//!
+//! Each operation is a lazy merge of the two trees' sorted `TreeIter`s.
+//! Trees may hold duplicates, so elements are treated as a multiset: equal
+//! elements from each side are paired off one-to-one as they are merged.
+
+use std::cmp::Ordering;
+use std::iter::Peekable;
+
+use crate::{BinaryTree, TreeIter};
+
+
+impl<T: Ord> BinaryTree<T> {
+    /// Elements in either tree, in ascending order.
+    pub fn union<'a>(&'a self, other: &'a BinaryTree<T>) -> Union<'a, T> {
+        Union { merge: Merge::new(self, other) }
+    }
+
+    /// Elements in both trees, in ascending order.
+    pub fn intersection<'a>(&'a self, other: &'a BinaryTree<T>) -> Intersection<'a, T> {
+        Intersection { merge: Merge::new(self, other) }
+    }
+
+    /// Elements in this tree but not in `other`, in ascending order.
+    pub fn difference<'a>(&'a self, other: &'a BinaryTree<T>) -> Difference<'a, T> {
+        Difference { merge: Merge::new(self, other) }
+    }
+
+    /// Elements in exactly one of the trees, in ascending order.
+    pub fn symmetric_difference<'a>(
+        &'a self,
+        other: &'a BinaryTree<T>,
+    ) -> SymmetricDifference<'a, T> {
+        SymmetricDifference { merge: Merge::new(self, other) }
+    }
+}
+
+
+/// Where the next element of a merge came from.
+enum Merged<'a, T> {
+    Left(&'a T),
+    Both(&'a T),
+    Right(&'a T),
+}
+
+
+/// Walk two sorted iterators in step, always taking the smaller element.
+#[derive(Debug)]
+struct Merge<'a, T> {
+    left: Peekable<TreeIter<'a, T>>,
+    right: Peekable<TreeIter<'a, T>>,
+}
+
+
+impl<'a, T: Ord> Merge<'a, T> {
+    fn new(left: &'a BinaryTree<T>, right: &'a BinaryTree<T>) -> Self {
+        Merge {
+            left: left.iter().peekable(),
+            right: right.iter().peekable(),
+        }
+    }
+}
+
+impl<'a, T: Ord> Iterator for Merge<'a, T> {
+    type Item = Merged<'a, T>;
+
+    fn next(&mut self) -> Option<Merged<'a, T>> {
+        let ordering = match (self.left.peek(), self.right.peek()) {
+            (None, None) => return None,
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(left), Some(right)) => left.cmp(right),
+        };
+        match ordering {
+            Ordering::Less => self.left.next().map(Merged::Left),
+            Ordering::Greater => self.right.next().map(Merged::Right),
+            Ordering::Equal => {
+                self.right.next();
+                self.left.next().map(Merged::Both)
+            },
+        }
+    }
+}
+
+
+/// Lazy iterator over the union of two `BinaryTree`s.
+#[derive(Debug)]
+pub struct Union<'a, T> {
+    merge: Merge<'a, T>,
+}
+
+
+impl<'a, T: Ord> Iterator for Union<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        match self.merge.next()? {
+            Merged::Left(element) | Merged::Both(element) | Merged::Right(element) => {
+                Some(element)
+            },
+        }
+    }
+}
+
+
+/// Lazy iterator over the intersection of two `BinaryTree`s.
+#[derive(Debug)]
+pub struct Intersection<'a, T> {
+    merge: Merge<'a, T>,
+}
+
+
+impl<'a, T: Ord> Iterator for Intersection<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.merge.find_map(|merged| match merged {
+            Merged::Both(element) => Some(element),
+            _ => None,
+        })
+    }
+}
+
+
+/// Lazy iterator over the difference of two `BinaryTree`s.
+#[derive(Debug)]
+pub struct Difference<'a, T> {
+    merge: Merge<'a, T>,
+}
+
+
+impl<'a, T: Ord> Iterator for Difference<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.merge.find_map(|merged| match merged {
+            Merged::Left(element) => Some(element),
+            _ => None,
+        })
+    }
+}
+
+
+/// Lazy iterator over the symmetric difference of two `BinaryTree`s.
+#[derive(Debug)]
+pub struct SymmetricDifference<'a, T> {
+    merge: Merge<'a, T>,
+}
+
+
+impl<'a, T: Ord> Iterator for SymmetricDifference<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.merge.find_map(|merged| match merged {
+            Merged::Left(element) | Merged::Right(element) => Some(element),
+            Merged::Both(_) => None,
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn create_pair() -> (BinaryTree<u32>, BinaryTree<u32>) {
+        let odd = BinaryTree::from_iter([5, 1, 9, 3, 7]);
+        let low = BinaryTree::from_iter([3, 2, 1, 4, 5]);
+        (odd, low)
+    }
+
+    #[test]
+    fn test_union() {
+        let (odd, low) = create_pair();
+        let v: Vec<_> = odd.union(&low).copied().collect();
+        assert_eq!(v, vec![1, 2, 3, 4, 5, 7, 9]);
+    }
+
+    #[test]
+    fn test_intersection() {
+        let (odd, low) = create_pair();
+        let v: Vec<_> = odd.intersection(&low).copied().collect();
+        assert_eq!(v, vec![1, 3, 5]);
+    }
+
+    #[test]
+    fn test_difference() {
+        let (odd, low) = create_pair();
+        let v: Vec<_> = odd.difference(&low).copied().collect();
+        assert_eq!(v, vec![7, 9]);
+        let v: Vec<_> = low.difference(&odd).copied().collect();
+        assert_eq!(v, vec![2, 4]);
+    }
+
+    #[test]
+    fn test_symmetric_difference() {
+        let (odd, low) = create_pair();
+        let v: Vec<_> = odd.symmetric_difference(&low).copied().collect();
+        assert_eq!(v, vec![2, 4, 7, 9]);
+    }
+
+    #[test]
+    fn test_with_empty() {
+        let (odd, _) = create_pair();
+        let empty = BinaryTree::empty();
+        assert_eq!(odd.union(&empty).count(), 5);
+        assert_eq!(empty.union(&odd).count(), 5);
+        assert_eq!(odd.intersection(&empty).count(), 0);
+        assert_eq!(odd.difference(&empty).count(), 5);
+        assert_eq!(empty.symmetric_difference(&odd).count(), 5);
+    }
+
+    #[test]
+    fn test_duplicates_pair_off() {
+        let a = BinaryTree::from_iter([1, 1, 1, 2]);
+        let b = BinaryTree::from_iter([1, 2, 2]);
+        assert_eq!(a.union(&b).copied().collect::<Vec<_>>(), vec![1, 1, 1, 2, 2]);
+        assert_eq!(a.intersection(&b).copied().collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(a.difference(&b).copied().collect::<Vec<_>>(), vec![1, 1]);
+        assert_eq!(
+            a.symmetric_difference(&b).copied().collect::<Vec<_>>(),
+            vec![1, 1, 2],
+        );
+    }
+}
@@ -0,0 +1,187 @@
+//! Pre-order, post-order, and level-order (breadth-first) traversals.
+//!
+//! Like `TreeIter`, these keep their own stack or queue of nodes rather than
+//! recursing, so they work on trees of any depth.
+
+use std::collections::VecDeque;
+
+use crate::{BinaryTree, TreeNode};
+
+
+impl<T> BinaryTree<T> {
+    /// Iterate over elements with each node before its left and right
+    /// sub-trees.
+    pub fn pre_order(&self) -> PreOrderIter<'_, T> {
+        let mut iter = PreOrderIter { unvisited: Vec::new() };
+        iter.push(self);
+        iter
+    }
+
+    /// Iterate over elements with each node after its left and right
+    /// sub-trees.
+    pub fn post_order(&self) -> PostOrderIter<'_, T> {
+        let mut iter = PostOrderIter { unvisited: Vec::new() };
+        iter.push(self);
+        iter
+    }
+
+    /// Iterate over elements one level at a time, starting at the root and
+    /// running left to right across each level.
+    pub fn level_order(&self) -> LevelOrderIter<'_, T> {
+        let mut iter = LevelOrderIter { unvisited: VecDeque::new() };
+        iter.push(self);
+        iter
+    }
+}
+
+
+/// Pre-order iterator over `BinaryTree`.
+#[derive(Debug)]
+pub struct PreOrderIter<'a, T> {
+    // Stack of nodes still to visit, next node on top.
+    unvisited: Vec<&'a TreeNode<T>>,
+}
+
+
+impl<'a, T> PreOrderIter<'a, T> {
+    fn push(&mut self, tree: &'a BinaryTree<T>) {
+        if let BinaryTree::NonEmpty(ref node) = *tree {
+            self.unvisited.push(node);
+        }
+    }
+}
+
+impl<'a, T> Iterator for PreOrderIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let node = self.unvisited.pop()?;
+
+        // Right pushed first so that left sub-tree is popped first
+        self.push(&node.right);
+        self.push(&node.left);
+
+        Some(&node.element)
+    }
+}
+
+
+/// Post-order iterator over `BinaryTree`.
+#[derive(Debug)]
+pub struct PostOrderIter<'a, T> {
+    // Stack of nodes, flagged true once their children have been pushed.
+    unvisited: Vec<(&'a TreeNode<T>, bool)>,
+}
+
+
+impl<'a, T> PostOrderIter<'a, T> {
+    fn push(&mut self, tree: &'a BinaryTree<T>) {
+        if let BinaryTree::NonEmpty(ref node) = *tree {
+            self.unvisited.push((node, false));
+        }
+    }
+}
+
+impl<'a, T> Iterator for PostOrderIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        loop {
+            let (node, expanded) = self.unvisited.pop()?;
+            if expanded {
+                return Some(&node.element);
+            }
+
+            // Revisit node once both of its sub-trees have been produced
+            self.unvisited.push((node, true));
+            self.push(&node.right);
+            self.push(&node.left);
+        }
+    }
+}
+
+
+/// Level-order (breadth-first) iterator over `BinaryTree`.
+#[derive(Debug)]
+pub struct LevelOrderIter<'a, T> {
+    // Queue of nodes to visit, in order.
+    unvisited: VecDeque<&'a TreeNode<T>>,
+}
+
+
+impl<'a, T> LevelOrderIter<'a, T> {
+    fn push(&mut self, tree: &'a BinaryTree<T>) {
+        if let BinaryTree::NonEmpty(ref node) = *tree {
+            self.unvisited.push_back(node);
+        }
+    }
+}
+
+impl<'a, T> Iterator for LevelOrderIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let node = self.unvisited.pop_front()?;
+        self.push(&node.left);
+        self.push(&node.right);
+        Some(&node.element)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    //         4
+    //       /   \
+    //      2     6
+    //     / \   / \
+    //    1   3 5   7
+    fn create_numbers() -> BinaryTree<u32> {
+        BinaryTree::from_iter([4, 2, 6, 1, 3, 5, 7])
+    }
+
+    /// Tree with only right children, as deep as it is long.
+    fn create_degenerate(len: u32) -> BinaryTree<u32> {
+        BinaryTree::from_iter(0..len)
+    }
+
+    #[test]
+    fn test_pre_order() {
+        let tree = create_numbers();
+        let v: Vec<_> = tree.pre_order().copied().collect();
+        assert_eq!(v, vec![4, 2, 1, 3, 6, 5, 7]);
+    }
+
+    #[test]
+    fn test_post_order() {
+        let tree = create_numbers();
+        let v: Vec<_> = tree.post_order().copied().collect();
+        assert_eq!(v, vec![1, 3, 2, 5, 7, 6, 4]);
+    }
+
+    #[test]
+    fn test_level_order() {
+        let tree = create_numbers();
+        let v: Vec<_> = tree.level_order().copied().collect();
+        assert_eq!(v, vec![4, 2, 6, 1, 3, 5, 7]);
+    }
+
+    #[test]
+    fn test_empty() {
+        let tree: BinaryTree<u32> = BinaryTree::empty();
+        assert_eq!(tree.pre_order().next(), None);
+        assert_eq!(tree.post_order().next(), None);
+        assert_eq!(tree.level_order().next(), None);
+    }
+
+    #[test]
+    fn test_deep_tree() {
+        let tree = create_degenerate(1_000);
+        assert_eq!(tree.pre_order().count(), 1_000);
+        assert_eq!(tree.post_order().next(), Some(&999));
+        assert_eq!(tree.level_order().last(), Some(&999));
+    }
+}
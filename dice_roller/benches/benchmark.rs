@@ -1,7 +1,14 @@
+use std::hint::black_box;
 
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
 
-use dice_roller::{build_array_manually, build_array_stdlib};
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+use dice_roller::{build_array_manually, build_array_stdlib, DiceRoller};
+
+
+const ROLLS: usize = 1_000_000;
 
 
 fn bench_fibonaccis(c: &mut Criterion) {
@@ -9,5 +16,39 @@ fn bench_fibonaccis(c: &mut Criterion) {
     c.bench_function("stdlib", |b| b.iter(|| build_array_stdlib(black_box(20))));
 }
 
-criterion_group!(benches, bench_fibonaccis);
+
+/// Fill a large buffer with rolls, in bulk and one die at a time.
+fn bench_rolls(c: &mut Criterion) {
+    let mut group = c.benchmark_group("rolls");
+    group.throughput(Throughput::Elements(ROLLS as u64));
+    let mut buffer = vec![0_u8; ROLLS];
+    for sides in [6_u8, 20, 100] {
+        group.bench_with_input(BenchmarkId::new("fill_rolls", sides), &sides, |b, &sides| {
+            let mut roller = DiceRoller::new(SmallRng::seed_from_u64(1));
+            b.iter(|| roller.fill_rolls(black_box(&mut buffer), sides));
+        });
+        group.bench_with_input(BenchmarkId::new("roll", sides), &sides, |b, &sides| {
+            let mut roller = DiceRoller::new(SmallRng::seed_from_u64(1));
+            b.iter(|| {
+                for roll in buffer.iter_mut() {
+                    *roll = roller.roll(u32::from(sides)) as u8;
+                }
+                black_box(&buffer);
+            });
+        });
+        group.bench_with_input(BenchmarkId::new("gen_range", sides), &sides, |b, &sides| {
+            let mut rng = SmallRng::seed_from_u64(1);
+            b.iter(|| {
+                for roll in buffer.iter_mut() {
+                    *roll = rng.gen_range(1..=sides);
+                }
+                black_box(&buffer);
+            });
+        });
+    }
+    group.finish();
+}
+
+
+criterion_group!(benches, bench_fibonaccis, bench_rolls);
 criterion_main!(benches);
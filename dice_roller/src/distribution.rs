@@ -0,0 +1,514 @@
+//! Exact probability distributions of dice expressions.
+//!
+//! Rather than rolling many times and counting, every possible outcome is
+//! weighed directly. Sums of dice are found by convolution, and keeping or
+//! dropping dice by dynamic programming over the faces of the die, from the
+//! highest face down (or the lowest face up).
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::notation::{Dice, Expr, Op, RollError, MAX_EXPLOSIONS};
+
+
+/// Rough limit on the number of arithmetic steps a single distribution may
+/// take to calculate, so that huge expressions fail quickly.
+pub const MAX_WORK: u128 = 200_000_000;
+
+/// Most distinct outcomes a distribution may have.
+pub const MAX_OUTCOMES: usize = 1 << 20;
+
+
+/// Probability of every outcome of an expression, from the smallest
+/// possible result to the largest.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Distribution {
+    min: i64,
+    probabilities: Vec<f64>,
+}
+
+
+/// Reasons a distribution can't be calculated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DistributionError {
+    /// Too many outcomes, or too much work to calculate them.
+    TooLarge,
+
+    /// Exploding dice add a random number of dice to their group, which
+    /// keep or drop modifiers then choose between.
+    KeepExploding,
+
+    /// Some outcome would divide by zero.
+    DivisionByZero,
+
+    /// Some outcome would overflow.
+    Overflow,
+}
+
+
+impl fmt::Display for DistributionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DistributionError::TooLarge => write!(f, "expression too large to analyse"),
+            DistributionError::KeepExploding => {
+                write!(f, "can't keep or drop from exploding dice")
+            },
+            DistributionError::DivisionByZero => write!(f, "division by zero is possible"),
+            DistributionError::Overflow => write!(f, "arithmetic overflow is possible"),
+        }
+    }
+}
+
+impl std::error::Error for DistributionError {}
+
+
+impl From<RollError> for DistributionError {
+    fn from(error: RollError) -> Self {
+        match error {
+            RollError::DivisionByZero => DistributionError::DivisionByZero,
+            RollError::Overflow => DistributionError::Overflow,
+        }
+    }
+}
+
+
+impl Expr {
+    /// Exact distribution of this expression's total.
+    pub fn distribution(&self) -> Result<Distribution, DistributionError> {
+        match self {
+            Expr::Number(n) => Ok(Distribution::constant(*n)),
+            Expr::Dice(dice) => dice.distribution(),
+            Expr::Neg(expr) => expr.distribution()?.neg(),
+            Expr::Binary(left, op, right) => {
+                let left = left.distribution()?;
+                let right = right.distribution()?;
+                match op {
+                    Op::Add => left.add(&right),
+                    Op::Sub => left.add(&right.neg()?),
+                    Op::Mul | Op::Div => left.combine(&right, *op),
+                }
+            },
+        }
+    }
+}
+
+
+impl Dice {
+    /// Exact distribution of this group's total.
+    pub fn distribution(&self) -> Result<Distribution, DistributionError> {
+        let die = self.die_distribution()?;
+        let count = self.count as usize;
+        match self.keep {
+            None => {
+                // Sum of `count` dice, adding one die at a time
+                let outcomes = die.probabilities.len() as u128;
+                check_work(count as u128 * count as u128 * outcomes * outcomes / 2)?;
+                let mut total = Distribution::constant(0);
+                for _ in 0..count {
+                    total = total.add(&die)?;
+                }
+                Ok(total)
+            },
+            Some(_) if self.explode => Err(DistributionError::KeepExploding),
+            Some(keep) => {
+                let (skip, take) = keep.range(count);
+                die.keep(count, take, skip == 0)
+            },
+        }
+    }
+
+    /// Distribution of a single die, allowing for rerolls and explosions.
+    fn die_distribution(&self) -> Result<Distribution, DistributionError> {
+        let sides = self.sides as usize;
+        let faces = (1..=self.sides).filter(|face| !self.reroll.contains(face)).count();
+        let mut face = vec![0.0; sides];
+        for (i, p) in face.iter_mut().enumerate() {
+            if !self.reroll.contains(&(i as u32 + 1)) {
+                *p = 1.0 / faces as f64;
+            }
+        }
+        let face = Distribution { min: 1, probabilities: face };
+        if !self.explode {
+            return Ok(face.trimmed());
+        }
+
+        // Build from the last explosion allowed back up to the first:
+        // each level is either a face below the highest, or the highest
+        // face plus the level below it.
+        let levels = MAX_EXPLOSIONS as usize + 1;
+        check_outcomes(sides * levels)?;
+        check_work((sides * levels * levels) as u128)?;
+        let highest = face.probabilities[sides - 1];
+        let mut die = face.clone();
+        for _ in 0..MAX_EXPLOSIONS {
+            let mut next = face.probabilities.clone();
+            next[sides - 1] = 0.0;
+            next.resize(sides + die.probabilities.len(), 0.0);
+            for (i, p) in die.probabilities.iter().enumerate() {
+                next[sides + i] += highest * p;
+            }
+            die = Distribution { min: 1, probabilities: next };
+        }
+        Ok(die.trimmed())
+    }
+}
+
+
+impl Distribution {
+    /// Distribution with one certain outcome.
+    pub fn constant(value: i64) -> Self {
+        Distribution { min: value, probabilities: vec![1.0] }
+    }
+
+    /// Smallest possible outcome.
+    pub fn min(&self) -> i64 {
+        self.min
+    }
+
+    /// Largest possible outcome.
+    pub fn max(&self) -> i64 {
+        self.min + self.probabilities.len() as i64 - 1
+    }
+
+    /// Probability of exactly `value`.
+    pub fn probability(&self, value: i64) -> f64 {
+        usize::try_from(value - self.min)
+            .ok()
+            .and_then(|i| self.probabilities.get(i))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    /// Every outcome from smallest to largest, with its probability.
+    pub fn iter(&self) -> impl Iterator<Item=(i64, f64)> + '_ {
+        let min = self.min;
+        self.probabilities.iter().enumerate().map(move |(i, &p)| (min + i as i64, p))
+    }
+
+    /// Expected value.
+    pub fn mean(&self) -> f64 {
+        self.iter().map(|(value, p)| value as f64 * p).sum()
+    }
+
+    /// Expected squared distance from the mean.
+    pub fn variance(&self) -> f64 {
+        let mean = self.mean();
+        self.iter().map(|(value, p)| (value as f64 - mean).powi(2) * p).sum()
+    }
+
+    /// Square root of the variance.
+    pub fn std_dev(&self) -> f64 {
+        self.variance().sqrt()
+    }
+
+    /// Smallest outcome which at least `percent` of results are equal to
+    /// or less than, eg. `percentile(50.0)` for the median.
+    pub fn percentile(&self, percent: f64) -> i64 {
+        let target = percent / 100.0 - 1e-12;
+        let mut cumulative = 0.0;
+        for (value, p) in self.iter() {
+            cumulative += p;
+            if cumulative >= target {
+                return value;
+            }
+        }
+        self.max()
+    }
+
+    /// Probability of a result of `target` or more.
+    pub fn at_least(&self, target: i64) -> f64 {
+        self.iter().filter(|&(value, _)| value >= target).map(|(_, p)| p).sum()
+    }
+
+    /// Probability of a result of `target` or less.
+    pub fn at_most(&self, target: i64) -> f64 {
+        self.iter().filter(|&(value, _)| value <= target).map(|(_, p)| p).sum()
+    }
+
+    /// Table of outcomes with their probabilities and horizontal bars,
+    /// scaled so that the most likely outcome's bar is `width` wide.
+    pub fn histogram(&self, width: usize) -> String {
+        const EIGHTHS: [&str; 8] = ["", "▏", "▎", "▍", "▌", "▋", "▊", "▉"];
+        let highest = self.probabilities.iter().copied().fold(0.0, f64::max);
+        let digits = self.min.to_string().len().max(self.max().to_string().len());
+        let mut text = String::new();
+        for (value, p) in self.iter() {
+            let eighths = (p / highest * width as f64 * 8.0).round() as usize;
+            let bar = "█".repeat(eighths / 8) + EIGHTHS[eighths % 8];
+            let line = format!("{value:>digits$} {:>7.3}% {bar}", p * 100.0);
+            text.push_str(line.trim_end());
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Distribution of the sum of outcomes from both distributions.
+    fn add(&self, other: &Self) -> Result<Self, DistributionError> {
+        let min = self.min.checked_add(other.min).ok_or(DistributionError::Overflow)?;
+        self.max().checked_add(other.max()).ok_or(DistributionError::Overflow)?;
+        let len = self.probabilities.len() + other.probabilities.len() - 1;
+        check_outcomes(len)?;
+        check_work((self.probabilities.len() * other.probabilities.len()) as u128)?;
+        let mut probabilities = vec![0.0; len];
+        for (i, a) in self.probabilities.iter().enumerate() {
+            for (j, b) in other.probabilities.iter().enumerate() {
+                probabilities[i + j] += a * b;
+            }
+        }
+        Ok(Distribution { min, probabilities })
+    }
+
+    fn neg(&self) -> Result<Self, DistributionError> {
+        let min = self.max().checked_neg().ok_or(DistributionError::Overflow)?;
+        let probabilities = self.probabilities.iter().rev().copied().collect();
+        Ok(Distribution { min, probabilities })
+    }
+
+    /// Apply operator to every pair of outcomes.
+    fn combine(&self, other: &Self, op: Op) -> Result<Self, DistributionError> {
+        check_work((self.probabilities.len() * other.probabilities.len()) as u128)?;
+        let mut outcomes: BTreeMap<i64, f64> = BTreeMap::new();
+        for (a, p) in self.iter().filter(|&(_, p)| p > 0.0) {
+            for (b, q) in other.iter().filter(|&(_, q)| q > 0.0) {
+                *outcomes.entry(op.apply(a, b)?).or_default() += p * q;
+            }
+        }
+        let (&min, _) = outcomes.first_key_value().unwrap();
+        let (&max, _) = outcomes.last_key_value().unwrap();
+        let len = usize::try_from(max.abs_diff(min) + 1).unwrap_or(usize::MAX);
+        check_outcomes(len)?;
+        let mut probabilities = vec![0.0; len];
+        for (value, p) in outcomes {
+            probabilities[(value - min) as usize] = p;
+        }
+        Ok(Distribution { min, probabilities })
+    }
+
+    /// Distribution of the sum of the `keep` highest (or lowest) of
+    /// `count` dice, each with this distribution.
+    ///
+    /// Faces are visited from the best to the worst. For every face, and
+    /// every number of dice already placed, choose how many of the rest show
+    /// that face. The first `keep` dice placed are the ones kept.
+    fn keep(&self, count: usize, keep: usize, highest: bool) -> Result<Self, DistributionError> {
+        let faces = self.probabilities.len();
+        let max_sum = keep * self.max() as usize;
+        check_outcomes(max_sum + 1)?;
+        check_work((faces * count * count * (max_sum + 1) / 2) as u128)?;
+
+        // Binomial coefficients, C(n, k)
+        let mut choose = vec![vec![1.0_f64; count + 1]; count + 1];
+        for n in 1..=count {
+            for k in 1..n {
+                choose[n][k] = choose[n - 1][k - 1] + choose[n - 1][k];
+            }
+        }
+
+        // ways[placed][sum], weighted by probability
+        let mut ways = vec![vec![0.0_f64; max_sum + 1]; count + 1];
+        ways[0][0] = 1.0;
+        let mut order: Vec<(i64, f64)> = self.iter().filter(|&(_, p)| p > 0.0).collect();
+        if highest {
+            order.reverse();
+        }
+        for (value, p) in order {
+            let value = value as usize;
+            let mut next = vec![vec![0.0_f64; max_sum + 1]; count + 1];
+            for placed in 0..=count {
+                for sum in 0..=max_sum {
+                    let weight = ways[placed][sum];
+                    if weight == 0.0 {
+                        continue;
+                    }
+                    let mut power = 1.0;
+                    for more in 0..=(count - placed) {
+                        let kept = (placed + more).min(keep) - placed.min(keep);
+                        next[placed + more][sum + kept * value] +=
+                            weight * choose[count - placed][more] * power;
+                        power *= p;
+                    }
+                }
+            }
+            ways = next;
+        }
+
+        let probabilities = ways.swap_remove(count);
+        Ok(Distribution { min: 0, probabilities }.trimmed())
+    }
+
+    /// Remove impossible outcomes from both ends.
+    fn trimmed(mut self) -> Self {
+        let start = self.probabilities.iter().position(|&p| p > 0.0).unwrap_or(0);
+        let end = self.probabilities.iter().rposition(|&p| p > 0.0).unwrap_or(0);
+        self.probabilities.truncate(end + 1);
+        self.probabilities.drain(..start);
+        self.min += start as i64;
+        self
+    }
+}
+
+
+fn check_work(steps: u128) -> Result<(), DistributionError> {
+    if steps > MAX_WORK {
+        Err(DistributionError::TooLarge)
+    } else {
+        Ok(())
+    }
+}
+
+
+fn check_outcomes(len: usize) -> Result<(), DistributionError> {
+    if len > MAX_OUTCOMES {
+        Err(DistributionError::TooLarge)
+    } else {
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::SeedableRng;
+    use rand::rngs::SmallRng;
+
+    use crate::DiceRoller;
+    use crate::notation::parse;
+
+    const EPSILON: f64 = 1e-9;
+
+    fn distribution(input: &str) -> Distribution {
+        parse(input).unwrap().distribution().unwrap()
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < EPSILON,
+            "expected {expected}, got {actual}",
+        );
+    }
+
+    fn assert_same(a: Distribution, b: Distribution) {
+        assert_eq!((a.min(), a.max()), (b.min(), b.max()));
+        for ((_, p), (_, q)) in a.iter().zip(b.iter()) {
+            assert_close(p, q);
+        }
+    }
+
+    #[test]
+    fn constant() {
+        let d = distribution("7");
+        assert_eq!((d.min(), d.max()), (7, 7));
+        assert_close(d.mean(), 7.0);
+        assert_close(d.variance(), 0.0);
+    }
+
+    #[test]
+    fn three_d6() {
+        let d = distribution("3d6");
+        assert_eq!((d.min(), d.max()), (3, 18));
+        assert_close(d.probability(10), 27.0 / 216.0);
+        assert_close(d.probability(3), 1.0 / 216.0);
+        assert_close(d.probability(2), 0.0);
+        assert_close(d.mean(), 10.5);
+        assert_close(d.variance(), 3.0 * 35.0 / 12.0);
+        assert_close(d.at_least(16), 10.0 / 216.0);
+        assert_close(d.at_most(4), 4.0 / 216.0);
+        assert_eq!(d.percentile(50.0), 10);
+        assert_eq!(d.percentile(0.0), 3);
+        assert_eq!(d.percentile(100.0), 18);
+    }
+
+    #[test]
+    fn four_d6_keep_highest_three() {
+        let d = distribution("4d6kh3");
+        assert_eq!((d.min(), d.max()), (3, 18));
+        assert_close(d.probability(18), 21.0 / 1296.0);
+        assert_close(d.probability(3), 1.0 / 1296.0);
+        assert_close(d.mean(), 15869.0 / 1296.0);
+    }
+
+    #[test]
+    fn drop_lowest_matches_keep_highest() {
+        assert_same(distribution("4d6dl1"), distribution("4d6kh3"));
+        assert_same(distribution("4d6dh1"), distribution("4d6kl3"));
+    }
+
+    #[test]
+    fn disadvantage_plus_five() {
+        // Lowest of 2d20: P(min >= k) = ((21 - k) / 20)^2
+        let d = distribution("2d20kl1+5");
+        assert_eq!((d.min(), d.max()), (6, 25));
+        assert_close(d.mean(), 5.0 + 2870.0 / 400.0);
+        assert_close(d.at_least(5 + 11), (10.0_f64 / 20.0).powi(2));
+    }
+
+    #[test]
+    fn keep_more_than_rolled() {
+        assert_same(distribution("2d6kh5"), distribution("2d6"));
+    }
+
+    #[test]
+    fn reroll() {
+        let d = distribution("1d6r1");
+        assert_eq!((d.min(), d.max()), (2, 6));
+        assert_close(d.mean(), 4.0);
+    }
+
+    #[test]
+    fn explode() {
+        let d = distribution("1d6!");
+        assert_close(d.probability(6), 0.0);
+        assert_close(d.probability(7), 1.0 / 36.0);
+        assert_close(d.mean(), 4.2);
+        assert_close(d.iter().map(|(_, p)| p).sum(), 1.0);
+    }
+
+    #[test]
+    fn arithmetic() {
+        let d = distribution("1d4-1d4");
+        assert_eq!((d.min(), d.max()), (-3, 3));
+        assert_close(d.probability(0), 4.0 / 16.0);
+
+        let d = distribution("1d6*2");
+        assert_close(d.probability(12), 1.0 / 6.0);
+        assert_close(d.probability(11), 0.0);
+
+        let d = distribution("-(1d6/2)");
+        assert_eq!((d.min(), d.max()), (-3, 0));
+        assert_close(d.probability(0), 1.0 / 6.0);
+    }
+
+    #[test]
+    fn errors() {
+        let error = |input: &str| parse(input).unwrap().distribution().unwrap_err();
+        assert_eq!(error("1d6/(1d2-1)"), DistributionError::DivisionByZero);
+        assert_eq!(error("4d6!kh3"), DistributionError::KeepExploding);
+        assert_eq!(error("1000d1000"), DistributionError::TooLarge);
+    }
+
+    #[test]
+    fn matches_simulation() {
+        let expr = parse("4d6kh3+1d4r1").unwrap();
+        let exact = expr.distribution().unwrap();
+        let mut roller = DiceRoller::new(SmallRng::seed_from_u64(42));
+        let count = 100_000;
+        let mut total = 0;
+        for _ in 0..count {
+            total += expr.roll(&mut roller).unwrap().total;
+        }
+        let mean = total as f64 / count as f64;
+        assert!((mean - exact.mean()).abs() < 0.05, "{mean} vs {}", exact.mean());
+    }
+
+    #[test]
+    fn histogram() {
+        let text = distribution("1d2+1d2").histogram(8);
+        assert_eq!(text, concat!(
+            "2  25.000% ████\n",
+            "3  50.000% ████████\n",
+            "4  25.000% ████\n",
+        ));
+    }
+}
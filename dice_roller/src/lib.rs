@@ -3,50 +3,152 @@
 use rand::RngCore;
 use rand::rngs::SmallRng;
 
+pub mod distribution;
+pub mod notation;
+pub mod summary;
 
+
+/// Roll dice of any size, using as few random bits as possible.
+///
+/// Random numbers are drawn 64 bits at a time from the wrapped generator,
+/// then handed out a few bits at a time. Rolls are unbiased: draws that
+/// can't be mapped evenly onto the sides of the die are rejected.
 #[derive(Debug)]
-struct DiceRoller {
-    rng: SmallRng,
-    buffer: [u8; 21],
-    current: usize,
+pub struct DiceRoller<R: RngCore = SmallRng> {
+    rng: R,
+    bits: u64,
+    available: u32,
 }
 
 
-impl DiceRoller {
-    fn new(rng: SmallRng) -> Self {
-        let buffer = [0_u8; 21];
-        let mut new = Self {
+impl<R: RngCore> DiceRoller<R> {
+    pub fn new(rng: R) -> Self {
+        Self {
             rng,
-            buffer,
-            current: 0,
-        };
-        new.refill_buffer();
-        new
+            bits: 0,
+            available: 0,
+        }
     }
 
+    /// Roll a six-sided die.
+    ///
+    /// Takes three bits at a time, rejecting the two values too large for a
+    /// d6. Faster than `roll(6)`, but uses more bits on average.
     pub fn d6(&mut self) -> u8 {
         loop {
-            if self.current == self.buffer.len() {
-                self.refill_buffer();
+            let roll = self.next_bits(3) as u8 + 1;
+            if roll < 7 {
+                break roll;
             }
+        }
+    }
 
-            let roll = self.buffer[self.current] + 1;
-            self.current += 1;
+    /// Roll a die with the given number of sides, from 1 to `sides`.
+    ///
+    /// Uses Lumbroso's *Fast Dice Roller*, which draws a single bit at a
+    /// time and carries the unused part of a rejected draw into the next
+    /// attempt. It needs fewer than `log2(sides) + 2` bits per roll on
+    /// average.
+    ///
+    /// Panics if `sides` is zero.
+    pub fn roll(&mut self, sides: u32) -> u32 {
+        assert!(sides > 0, "a die must have at least one side");
+        let sides = u64::from(sides);
 
-            if roll < 7 {
-                break roll;
+        // `candidate` is uniformly distributed over `0..range`
+        let mut range: u64 = 1;
+        let mut candidate: u64 = 0;
+        loop {
+            range <<= 1;
+            candidate = (candidate << 1) | self.next_bits(1);
+            if range >= sides {
+                if candidate < sides {
+                    break candidate as u32 + 1;
+                }
+
+                // Rejected, but `candidate - sides` is still uniform over
+                // what's left of the range, so keep it.
+                range -= sides;
+                candidate -= sides;
             }
         }
     }
 
-    fn refill_buffer(&mut self) {
-        let mut number = self.rng.next_u64();
-        for i in 0..21 {
-            self.buffer[i] = (number & 0x07) as u8;
-            number = number >> 3;
+    /// Fill `buffer` with rolls of a die with the given number of sides.
+    ///
+    /// Much faster than rolling one die at a time. Each 64-bit draw from the
+    /// generator is treated as a fraction, and its first few digits in base
+    /// `sides` become rolls, found by multiplication rather than division.
+    /// Following Lemire, a draw is only rejected if those digits would be
+    /// biased, so the cost of rejection is shared by every roll in the draw.
+    ///
+    /// Draws come straight from the generator, leaving the bits buffered
+    /// for `d6()` and `roll()` untouched.
+    ///
+    /// Panics if `sides` is zero.
+    pub fn fill_rolls(&mut self, buffer: &mut [u8], sides: u8) {
+        assert!(sides > 0, "a die must have at least one side");
+        if sides == 1 {
+            buffer.fill(1);
+            return;
         }
-        self.current = 0;
+        let (digits, power) = packing(sides);
+        let threshold = (1_u128 << u64::BITS) % power;
+        let sides = u128::from(sides);
+        for chunk in buffer.chunks_mut(digits) {
+            let mut fraction = loop {
+                let draw = self.rng.next_u64();
+                if u128::from((u128::from(draw) * power) as u64) >= threshold {
+                    break draw;
+                }
+            };
+            for roll in chunk {
+                let product = u128::from(fraction) * sides;
+                *roll = (product >> u64::BITS) as u8 + 1;
+                fraction = product as u64;
+            }
+        }
+    }
+
+    /// Take the next `count` random bits from the buffer, refilling it
+    /// first if too few remain.
+    fn next_bits(&mut self, count: u32) -> u64 {
+        if self.available < count {
+            self.bits = self.rng.next_u64();
+            self.available = u64::BITS;
+        }
+        let bits = self.bits & ((1 << count) - 1);
+        self.bits >>= count;
+        self.available -= count;
+        bits
+    }
+}
+
+
+/// How many rolls of a die to take from each 64-bit draw, along with
+/// `sides` to the power of that many rolls.
+///
+/// Packing in the most rolls possible can mean rejecting many draws, eg. a
+/// quarter of them for d6, so choose the number that gives the most rolls
+/// per draw on average.
+fn packing(sides: u8) -> (usize, u128) {
+    let range = 1_u128 << u64::BITS;
+    let sides = u128::from(sides);
+    let mut best = (1, sides);
+    let mut best_rate = 0.0;
+    let mut power = sides;
+    let mut digits = 1;
+    while power <= range {
+        let accepted = range - range % power;
+        let rate = digits as f64 * accepted as f64 / range as f64;
+        if rate > best_rate {
+            best = (digits, power);
+            best_rate = rate;
+        }
+        power *= sides;
+        digits += 1;
     }
+    best
 }
 
 
@@ -54,9 +156,9 @@ impl DiceRoller {
 pub fn build_array_manually(number: u64) -> [u8; 16] {
     let mut number = number;
     let mut rolls: [u8; 16] = [0_u8; 16];
-    for i in 0..16 {
-        rolls[i] = (number & 0x0f) as u8;
-        number = number >> 4;
+    for roll in &mut rolls {
+        *roll = (number & 0x0f) as u8;
+        number >>= 4;
     }
     rolls
 }
@@ -95,6 +197,58 @@ mod tests {
         DiceRoller::new(rng_bad)
     }
 
+    /// Critical values of the chi-squared distribution at p = 0.001, for the
+    /// degrees of freedom (sides - 1) of the dice tested.
+    const CHI_SQUARED_CRITICAL: [(u32, f64); 7] = [
+        (4, 16.266),
+        (6, 20.515),
+        (8, 24.322),
+        (10, 27.877),
+        (12, 31.264),
+        (20, 43.820),
+        (100, 148.230),
+    ];
+
+    /// Generator which counts how many 64-bit words have been taken from it
+    struct CountingRng {
+        inner: SmallRng,
+        words: usize,
+    }
+
+    impl RngCore for CountingRng {
+        fn next_u32(&mut self) -> u32 {
+            self.next_u64() as u32
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            self.words += 1;
+            self.inner.next_u64()
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            self.inner.fill_bytes(dest)
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+            self.inner.try_fill_bytes(dest)
+        }
+    }
+
+    /// Pearson's chi-squared statistic for `count` rolls of a die, against
+    /// the uniform distribution.
+    fn chi_squared(roller: &mut DiceRoller, sides: u32, count: usize) -> f64 {
+        let mut observed = vec![0_usize; sides as usize];
+        for _ in 0..count {
+            let roll = roller.roll(sides);
+            assert!((1..=sides).contains(&roll), "d{sides} rolled {roll}");
+            observed[roll as usize - 1] += 1;
+        }
+        let expected = count as f64 / sides as f64;
+        observed.iter()
+            .map(|&o| (o as f64 - expected).powi(2) / expected)
+            .sum()
+    }
+
     /// Build a vector of `count` d6 rolls
     fn roll_many(count: usize) -> Vec<u8> {
         let mut roller = create_roller();
@@ -120,6 +274,140 @@ mod tests {
         assert_eq!(roll_many(40), expected);
     }
 
+    #[test]
+    fn roll_d1() {
+        let mut roller = create_roller();
+        assert!((0..100).all(|_| roller.roll(1) == 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one side")]
+    fn roll_d0() {
+        create_roller().roll(0);
+    }
+
+    #[test]
+    fn roll_large_die() {
+        let mut roller = create_roller();
+        let sides = u32::MAX;
+        assert!((0..100).all(|_| roller.roll(sides) >= 1));
+    }
+
+    #[test]
+    fn roll_chi_squared() {
+        let mut roller = DiceRoller::new(SmallRng::seed_from_u64(42));
+        for (sides, critical) in CHI_SQUARED_CRITICAL {
+            let statistic = chi_squared(&mut roller, sides, 1_000 * sides as usize);
+            assert!(
+                statistic < critical,
+                "d{sides} not uniform: chi-squared {statistic:.2} >= {critical}",
+            );
+        }
+    }
+
+    #[test]
+    fn d6_chi_squared() {
+        let mut roller = DiceRoller::new(SmallRng::seed_from_u64(42));
+        let mut observed = [0_usize; 6];
+        for _ in 0..60_000 {
+            observed[roller.d6() as usize - 1] += 1;
+        }
+        let statistic: f64 = observed.iter()
+            .map(|&o| (o as f64 - 10_000.0).powi(2) / 10_000.0)
+            .sum();
+        assert!(statistic < 20.515, "d6 not uniform: chi-squared {statistic:.2}");
+    }
+
+    #[test]
+    fn roll_bits_used() {
+        // Average bits per roll must stay under log2(sides) + 2
+        for (sides, _) in CHI_SQUARED_CRITICAL {
+            let rng = CountingRng { inner: SmallRng::seed_from_u64(7), words: 0 };
+            let mut roller = DiceRoller::new(rng);
+            let count = 10_000;
+            for _ in 0..count {
+                roller.roll(sides);
+            }
+            let bits = (roller.rng.words * 64) as f64 / count as f64;
+            let limit = (sides as f64).log2() + 2.0;
+            assert!(bits < limit, "d{sides} used {bits:.2} bits per roll");
+        }
+    }
+
+    #[test]
+    fn packing_digits() {
+        // Powers of two pack without rejection
+        assert_eq!(packing(2), (64, 1 << 64));
+        assert_eq!(packing(16), (16, 1 << 64));
+        let (digits, _) = packing(6);
+        assert!((20..=24).contains(&digits), "d6 packed {digits} digits");
+        assert_eq!(packing(255).0, 8);
+    }
+
+    #[test]
+    fn fill_rolls_chi_squared() {
+        let mut roller = DiceRoller::new(SmallRng::seed_from_u64(42));
+        for (sides, critical) in CHI_SQUARED_CRITICAL {
+            let mut buffer = vec![0_u8; 1_000 * sides as usize];
+            roller.fill_rolls(&mut buffer, sides as u8);
+            let mut observed = vec![0_usize; sides as usize];
+            for &roll in &buffer {
+                assert!((1..=sides as u8).contains(&roll), "d{sides} rolled {roll}");
+                observed[roll as usize - 1] += 1;
+            }
+            let statistic: f64 = observed.iter()
+                .map(|&o| (o as f64 - 1_000.0).powi(2) / 1_000.0)
+                .sum();
+            assert!(
+                statistic < critical,
+                "d{sides} not uniform: chi-squared {statistic:.2} >= {critical}",
+            );
+        }
+    }
+
+    #[test]
+    fn fill_rolls_largest_die() {
+        let mut roller = create_roller();
+        let mut buffer = [0_u8; 10_000];
+        roller.fill_rolls(&mut buffer, u8::MAX);
+        assert!(buffer.iter().all(|&roll| roll >= 1));
+        assert!(buffer.contains(&u8::MAX));
+    }
+
+    #[test]
+    fn fill_rolls_d1() {
+        let mut roller = create_roller();
+        let mut buffer = [0_u8; 100];
+        roller.fill_rolls(&mut buffer, 1);
+        assert!(buffer.iter().all(|&roll| roll == 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one side")]
+    fn fill_rolls_d0() {
+        create_roller().fill_rolls(&mut [0; 4], 0);
+    }
+
+    #[test]
+    fn fill_rolls_is_reproducible() {
+        let mut a = [0_u8; 1_000];
+        let mut b = [0_u8; 1_000];
+        DiceRoller::new(SmallRng::seed_from_u64(9)).fill_rolls(&mut a, 20);
+        DiceRoller::new(SmallRng::seed_from_u64(9)).fill_rolls(&mut b, 20);
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn fill_rolls_draws_used() {
+        // d6 should pack at least twenty rolls into each draw, on average
+        let rng = CountingRng { inner: SmallRng::seed_from_u64(7), words: 0 };
+        let mut roller = DiceRoller::new(rng);
+        let mut buffer = vec![0_u8; 100_000];
+        roller.fill_rolls(&mut buffer, 6);
+        let per_draw = buffer.len() as f64 / roller.rng.words as f64;
+        assert!(per_draw > 20.0, "only {per_draw:.2} rolls per draw");
+    }
+
     #[test]
     fn build_array_manually_zero() {
         let expected = [0_u8; 16];
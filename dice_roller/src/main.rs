@@ -1,7 +1,181 @@
 
-const NUMBER: u64 = 0x1827364554637281_u64;
+use clap::{Parser, ValueEnum};
+
+use rand::rngs::SmallRng;
+use rand::SeedableRng;
+
+use serde_json::{json, Value};
+
+use dice_roller::notation::{self, DiceGroup, DieState, Expr, Roll};
+use dice_roller::summary::Summary;
+use dice_roller::DiceRoller;
+
+
+/// Roll dice, or analyse their exact odds
+#[derive(Parser, Debug)]
+#[command(version, about)]
+struct Args {
+    /// Dice expression, eg. "4d6kh3" or "2d20kl1+5"
+    expression: String,
+
+    /// Number of times to roll
+    #[arg(short, long, default_value_t=1)]
+    times: usize,
+
+    /// Seed the random number generator, to repeat an earlier run
+    #[arg(short, long)]
+    seed: Option<u64>,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t=Format::Text)]
+    format: Format,
+
+    /// Print the probability of every result instead of rolling
+    #[arg(short, long)]
+    distribution: bool,
+}
+
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+}
+
 
 fn main() {
-    dbg!(&dice_roller::build_array_manually(NUMBER));
-    dbg!(&dice_roller::build_array_stdlib(NUMBER));
+    let args = Args::parse();
+    let expr = match notation::parse(&args.expression) {
+        Ok(expr) => expr,
+        Err(error) => {
+            eprintln!("{}", error.annotate(&args.expression));
+            std::process::exit(2);
+        },
+    };
+
+    let result = if args.distribution {
+        print_distribution(&expr, args.format)
+    } else {
+        roll(&expr, &args)
+    };
+    if let Err(error) = result {
+        eprintln!("{error}");
+        std::process::exit(1);
+    }
+}
+
+
+/// Histogram of every possible result, with summary statistics.
+fn print_distribution(expr: &Expr, format: Format) -> Result<(), Box<dyn std::error::Error>> {
+    let distribution = expr.distribution()?;
+    match format {
+        Format::Text => {
+            println!("{expr}");
+            print!("{}", distribution.histogram(50));
+            println!();
+            println!("Range:     {} to {}", distribution.min(), distribution.max());
+            println!("Mean:      {:.3}", distribution.mean());
+            println!("Std dev:   {:.3}", distribution.std_dev());
+            println!(
+                "Quartiles: {} / {} / {}",
+                distribution.percentile(25.0),
+                distribution.percentile(50.0),
+                distribution.percentile(75.0),
+            );
+        },
+        Format::Json => {
+            let probabilities: Vec<Value> = distribution
+                .iter()
+                .map(|(total, p)| json!({ "total": total, "probability": p }))
+                .collect();
+            let output = json!({
+                "expression": expr.to_string(),
+                "mean": distribution.mean(),
+                "std_dev": distribution.std_dev(),
+                "probabilities": probabilities,
+            });
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        },
+    }
+    Ok(())
+}
+
+
+/// Roll as many times as asked, then summarise.
+///
+/// Without a seed one is chosen at random, and reported, so that any run
+/// can be repeated exactly.
+fn roll(expr: &Expr, args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let mut roller = DiceRoller::new(SmallRng::seed_from_u64(seed));
+    let rolls = (0..args.times)
+        .map(|_| expr.roll(&mut roller))
+        .collect::<Result<Vec<Roll>, _>>()?;
+    let totals: Vec<i64> = rolls.iter().map(|roll| roll.total).collect();
+    let summary = Summary::new(&totals);
+
+    match args.format {
+        Format::Text => {
+            let width = args.times.to_string().len();
+            for (i, roll) in rolls.iter().enumerate() {
+                let groups: Vec<String> = roll.groups.iter().map(DiceGroup::to_string).collect();
+                if args.times == 1 {
+                    println!("{}", groups.join("; "));
+                    println!("Total: {}", roll.total);
+                } else {
+                    println!("{:>width$}: {:>4}  {}", i + 1, roll.total, groups.join("; "));
+                }
+            }
+            if let Some(summary) = summary.filter(|_| args.times > 1) {
+                println!();
+                println!("Rolls:     {}", summary.count);
+                println!("Range:     {} to {}", summary.min, summary.max);
+                println!("Mean:      {:.3}", summary.mean);
+                if let Ok(distribution) = expr.distribution() {
+                    println!("Expected:  {:.3}", distribution.mean());
+                }
+                println!("Median:    {}", summary.median);
+                println!("Std dev:   {:.3}", summary.std_dev);
+            }
+
+            // Shown under the statistics, and otherwise whenever it was chosen
+            // at random, so even a single roll can be repeated
+            if args.times > 1 || args.seed.is_none() {
+                println!("Seed:      {seed}");
+            }
+        },
+        Format::Json => {
+            let output = json!({
+                "expression": expr.to_string(),
+                "seed": seed,
+                "rolls": rolls.iter().map(roll_json).collect::<Vec<_>>(),
+                "summary": summary.map(|summary| json!({
+                    "count": summary.count,
+                    "min": summary.min,
+                    "max": summary.max,
+                    "mean": summary.mean,
+                    "median": summary.median,
+                    "std_dev": summary.std_dev,
+                })),
+            });
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        },
+    }
+    Ok(())
+}
+
+
+fn roll_json(roll: &Roll) -> Value {
+    let groups: Vec<Value> = roll.groups.iter().map(|group| {
+        let dice: Vec<Value> = group.rolls.iter().map(|die| {
+            let state = match die.state {
+                DieState::Kept => "kept",
+                DieState::Dropped => "dropped",
+                DieState::Rerolled => "rerolled",
+            };
+            json!({ "value": die.value, "state": state, "exploded": die.exploded })
+        }).collect();
+        json!({ "dice": group.dice.to_string(), "rolls": dice, "total": group.total })
+    }).collect();
+    json!({ "total": roll.total, "groups": groups })
 }
@@ -0,0 +1,914 @@
+//! Standard dice notation, eg. `3d6+2`, `4d6kh3`, or `(1d8+1d6)*2`.
+//!
+//! ```text
+//! expression := term (('+' | '-') term)*
+//! term       := factor (('*' | '/') factor)*
+//! factor     := '-' factor | '(' expression ')' | number | dice
+//! dice       := [count] 'd' (sides | '%') modifier*
+//! modifier   := 'kh' n | 'kl' n | 'k' n | 'dh' n | 'dl' n | '!' | 'r' n
+//! ```
+//!
+//! Modifiers keep or drop the highest or lowest dice (`kh3`, `dl1`),
+//! explode dice showing their highest face by rolling again and adding
+//! (`!`), or reroll dice showing a given face until they don't (`r1`).
+
+use std::fmt;
+use std::str::FromStr;
+
+use rand::RngCore;
+
+use crate::DiceRoller;
+
+
+/// Most dice allowed in a single group, eg. `1000d6`.
+pub const MAX_DICE: u32 = 1000;
+
+/// Most sides allowed on a single die.
+pub const MAX_SIDES: u32 = 1_000_000;
+
+/// Most extra dice a single exploding die may add.
+pub const MAX_EXPLOSIONS: u32 = 100;
+
+
+/// Parsed dice expression
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expr {
+    Number(i64),
+    Dice(Dice),
+    Neg(Box<Expr>),
+    Binary(Box<Expr>, Op, Box<Expr>),
+}
+
+
+/// Arithmetic operators, applied to whole numbers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+
+/// A group of identical dice, rolled together, eg. `4d6kh3`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Dice {
+    pub count: u32,
+    pub sides: u32,
+    pub keep: Option<Keep>,
+    pub explode: bool,
+
+    /// Faces which are rerolled until some other face comes up.
+    pub reroll: Vec<u32>,
+}
+
+
+/// Which dice from a group count towards its total.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Keep {
+    Highest(u32),
+    Lowest(u32),
+    DropHighest(u32),
+    DropLowest(u32),
+}
+
+
+impl Keep {
+    /// How many of `count` dice, sorted highest first, to skip before
+    /// keeping, and how many to keep.
+    pub fn range(&self, count: usize) -> (usize, usize) {
+        let clamp = |n: u32| (n as usize).min(count);
+        match *self {
+            Keep::Highest(n) => (0, clamp(n)),
+            Keep::Lowest(n) => (count - clamp(n), clamp(n)),
+            Keep::DropHighest(n) => (clamp(n), count - clamp(n)),
+            Keep::DropLowest(n) => (0, count - clamp(n)),
+        }
+    }
+}
+
+
+impl Expr {
+    /// Roll every group of dice in the expression and calculate the total.
+    pub fn roll<R: RngCore>(&self, roller: &mut DiceRoller<R>) -> Result<Roll, RollError> {
+        let mut groups = Vec::new();
+        let total = self.evaluate(roller, &mut groups)?;
+        Ok(Roll { total, groups })
+    }
+
+    fn evaluate<R: RngCore>(
+        &self,
+        roller: &mut DiceRoller<R>,
+        groups: &mut Vec<DiceGroup>,
+    ) -> Result<i64, RollError> {
+        match self {
+            Expr::Number(n) => Ok(*n),
+            Expr::Dice(dice) => {
+                let group = dice.roll(roller);
+                let total = group.total;
+                groups.push(group);
+                Ok(total)
+            },
+            Expr::Neg(expr) => {
+                expr.evaluate(roller, groups)?.checked_neg().ok_or(RollError::Overflow)
+            },
+            Expr::Binary(left, op, right) => {
+                let left = left.evaluate(roller, groups)?;
+                let right = right.evaluate(roller, groups)?;
+                op.apply(left, right)
+            },
+        }
+    }
+}
+
+
+impl Op {
+    /// Apply operator, with integer division rounding towards zero.
+    pub fn apply(self, left: i64, right: i64) -> Result<i64, RollError> {
+        let result = match self {
+            Op::Add => left.checked_add(right),
+            Op::Sub => left.checked_sub(right),
+            Op::Mul => left.checked_mul(right),
+            Op::Div => {
+                if right == 0 {
+                    return Err(RollError::DivisionByZero);
+                }
+                left.checked_div(right)
+            },
+        };
+        result.ok_or(RollError::Overflow)
+    }
+
+    fn symbol(self) -> char {
+        match self {
+            Op::Add => '+',
+            Op::Sub => '-',
+            Op::Mul => '*',
+            Op::Div => '/',
+        }
+    }
+
+    fn precedence(self) -> u8 {
+        match self {
+            Op::Add | Op::Sub => 1,
+            Op::Mul | Op::Div => 2,
+        }
+    }
+}
+
+
+impl Dice {
+    /// Plain group of dice, without any modifiers.
+    pub fn new(count: u32, sides: u32) -> Self {
+        Dice { count, sides, keep: None, explode: false, reroll: Vec::new() }
+    }
+
+    /// Roll every die in the group, then apply keep or drop modifier.
+    pub fn roll<R: RngCore>(&self, roller: &mut DiceRoller<R>) -> DiceGroup {
+        let mut rolls = Vec::new();
+        for _ in 0..self.count {
+            let mut explosions = 0;
+            loop {
+                let value = self.roll_one(roller, &mut rolls);
+                let explodes = self.explode
+                    && value == self.sides
+                    && explosions < MAX_EXPLOSIONS;
+                rolls.push(Die { value, state: DieState::Kept, exploded: explodes });
+                if !explodes {
+                    break;
+                }
+                explosions += 1;
+            }
+        }
+
+        if let Some(keep) = self.keep {
+            // Sort indices of counted dice, highest value first
+            let mut counted: Vec<usize> = (0..rolls.len())
+                .filter(|&i| rolls[i].state == DieState::Kept)
+                .collect();
+            counted.sort_by(|&a, &b| rolls[b].value.cmp(&rolls[a].value));
+            let (skip, take) = keep.range(counted.len());
+            for (rank, &i) in counted.iter().enumerate() {
+                if rank < skip || rank >= skip + take {
+                    rolls[i].state = DieState::Dropped;
+                }
+            }
+        }
+
+        let total = rolls.iter()
+            .filter(|die| die.state == DieState::Kept)
+            .map(|die| i64::from(die.value))
+            .sum();
+        DiceGroup { dice: self.clone(), rolls, total }
+    }
+
+    /// Roll a single die, recording and rerolling any faces to be rerolled.
+    fn roll_one<R: RngCore>(
+        &self,
+        roller: &mut DiceRoller<R>,
+        rolls: &mut Vec<Die>,
+    ) -> u32 {
+        loop {
+            let value = roller.roll(self.sides);
+            if !self.reroll.contains(&value) {
+                break value;
+            }
+            rolls.push(Die { value, state: DieState::Rerolled, exploded: false });
+        }
+    }
+}
+
+
+/// Outcome of rolling a whole expression.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Roll {
+    pub total: i64,
+
+    /// Every group of dice rolled, in the order they appear.
+    pub groups: Vec<DiceGroup>,
+}
+
+
+/// Outcome of rolling a single group of dice.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiceGroup {
+    pub dice: Dice,
+
+    /// Every die rolled, including those dropped, rerolled, or added by
+    /// explosions, in the order they were rolled.
+    pub rolls: Vec<Die>,
+
+    /// Sum of the kept dice.
+    pub total: i64,
+}
+
+
+/// A single die thrown.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Die {
+    pub value: u32,
+    pub state: DieState,
+
+    /// Did this die show its highest face, and so add another die?
+    pub exploded: bool,
+}
+
+
+/// Does a die count towards its group's total?
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DieState {
+    Kept,
+    Dropped,
+    Rerolled,
+}
+
+
+/// Problem evaluating an expression, after dice have been rolled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RollError {
+    DivisionByZero,
+    Overflow,
+}
+
+
+impl fmt::Display for RollError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RollError::DivisionByZero => write!(f, "division by zero"),
+            RollError::Overflow => write!(f, "arithmetic overflow"),
+        }
+    }
+}
+
+impl std::error::Error for RollError {}
+
+
+/// Problem found while parsing, with its byte offset into the input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub position: usize,
+    pub kind: ParseErrorKind,
+}
+
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    Empty,
+    UnexpectedChar(char),
+    UnexpectedEnd,
+    ExpectedNumber,
+    NumberTooLarge,
+    UnclosedParen,
+    TooManyDice,
+    BadSides(u32),
+    DuplicateKeep,
+    DuplicateExplode,
+    ExplodeOneSided,
+    RerollOutOfRange(u32),
+    RerollEverything,
+}
+
+
+impl ParseError {
+    pub fn new(position: usize, kind: ParseErrorKind) -> Self {
+        ParseError { position, kind }
+    }
+
+    /// Error message with the input and a caret pointing at the problem.
+    ///
+    /// ```text
+    /// 4d6x
+    ///    ^ unexpected character 'x'
+    /// ```
+    pub fn annotate(&self, input: &str) -> String {
+        let column = input[..self.position.min(input.len())].chars().count();
+        format!("{input}\n{:column$}^ {}", "", self.kind)
+    }
+}
+
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.kind, self.position)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseErrorKind::Empty => write!(f, "empty expression"),
+            ParseErrorKind::UnexpectedChar(c) => write!(f, "unexpected character '{c}'"),
+            ParseErrorKind::UnexpectedEnd => write!(f, "unexpected end of expression"),
+            ParseErrorKind::ExpectedNumber => write!(f, "expected a number"),
+            ParseErrorKind::NumberTooLarge => write!(f, "number too large"),
+            ParseErrorKind::UnclosedParen => write!(f, "unclosed parenthesis"),
+            ParseErrorKind::TooManyDice => {
+                write!(f, "too many dice, at most {MAX_DICE} allowed")
+            },
+            ParseErrorKind::BadSides(n) => {
+                write!(f, "dice must have between 1 and {MAX_SIDES} sides, not {n}")
+            },
+            ParseErrorKind::DuplicateKeep => {
+                write!(f, "only one keep or drop modifier allowed")
+            },
+            ParseErrorKind::DuplicateExplode => write!(f, "dice can only explode once"),
+            ParseErrorKind::ExplodeOneSided => write!(f, "one-sided dice can't explode"),
+            ParseErrorKind::RerollOutOfRange(n) => write!(f, "dice can never roll {n}"),
+            ParseErrorKind::RerollEverything => write!(f, "every face would be rerolled"),
+        }
+    }
+}
+
+
+/// Parse dice expression, eg. `4d6kh3+2`.
+pub fn parse(input: &str) -> Result<Expr, ParseError> {
+    let mut parser = Parser { input: input.as_bytes(), position: 0 };
+    parser.skip_whitespace();
+    if parser.peek().is_none() {
+        return Err(parser.error(ParseErrorKind::Empty));
+    }
+    let expr = parser.expression()?;
+    parser.skip_whitespace();
+    match parser.peek() {
+        None => Ok(expr),
+        Some(b')') => Err(parser.error(ParseErrorKind::UnexpectedChar(')'))),
+        Some(_) => Err(parser.unexpected()),
+    }
+}
+
+
+impl FromStr for Expr {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse(s)
+    }
+}
+
+
+/// Constructor for a `Keep` variant, given its count.
+type MakeKeep = fn(u32) -> Keep;
+
+
+/// Recursive-descent parser, one function per rule of the grammar.
+struct Parser<'a> {
+    input: &'a [u8],
+    position: usize,
+}
+
+
+impl<'a> Parser<'a> {
+    fn expression(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.term()?;
+        loop {
+            self.skip_whitespace();
+            let op = match self.peek() {
+                Some(b'+') => Op::Add,
+                Some(b'-') => Op::Sub,
+                _ => break Ok(expr),
+            };
+            self.position += 1;
+            let right = self.term()?;
+            expr = Expr::Binary(Box::new(expr), op, Box::new(right));
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.factor()?;
+        loop {
+            self.skip_whitespace();
+            let op = match self.peek() {
+                Some(b'*') => Op::Mul,
+                Some(b'/') => Op::Div,
+                _ => break Ok(expr),
+            };
+            self.position += 1;
+            let right = self.factor()?;
+            expr = Expr::Binary(Box::new(expr), op, Box::new(right));
+        }
+    }
+
+    fn factor(&mut self) -> Result<Expr, ParseError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'-') => {
+                self.position += 1;
+                Ok(Expr::Neg(Box::new(self.factor()?)))
+            },
+            Some(b'(') => {
+                let open = self.position;
+                self.position += 1;
+                let expr = self.expression()?;
+                self.skip_whitespace();
+                if self.peek() != Some(b')') {
+                    return Err(ParseError::new(open, ParseErrorKind::UnclosedParen));
+                }
+                self.position += 1;
+                Ok(expr)
+            },
+            Some(b'd') => self.dice(1),
+            Some(c) if c.is_ascii_digit() => {
+                let start = self.position;
+                let number = self.number()?;
+                if self.peek() == Some(b'd') {
+                    if number > i64::from(MAX_DICE) {
+                        return Err(ParseError::new(start, ParseErrorKind::TooManyDice));
+                    }
+                    self.dice(number as u32)
+                } else {
+                    Ok(Expr::Number(number))
+                }
+            },
+            None => Err(self.error(ParseErrorKind::UnexpectedEnd)),
+            Some(_) => Err(self.unexpected()),
+        }
+    }
+
+    /// Parse from the `d` onwards, given the count already read.
+    fn dice(&mut self, count: u32) -> Result<Expr, ParseError> {
+        self.position += 1;
+        let sides = if self.peek() == Some(b'%') {
+            self.position += 1;
+            100
+        } else {
+            self.small_number()?
+        };
+        if !(1..=MAX_SIDES).contains(&sides) {
+            return Err(self.error_before(ParseErrorKind::BadSides(sides)));
+        }
+
+        let mut dice = Dice::new(count, sides);
+        loop {
+            let start = self.position;
+            let keep: Option<(usize, MakeKeep)> = match (self.peek(), self.peek_at(1)) {
+                (Some(b'k'), Some(b'h')) => Some((2, Keep::Highest)),
+                (Some(b'k'), Some(b'l')) => Some((2, Keep::Lowest)),
+                (Some(b'k'), _) => Some((1, Keep::Highest)),
+                (Some(b'd'), Some(b'h')) => Some((2, Keep::DropHighest)),
+                (Some(b'd'), Some(b'l')) => Some((2, Keep::DropLowest)),
+                _ => None,
+            };
+            if let Some((length, keep)) = keep {
+                self.position += length;
+                let n = self.small_number()?;
+                if dice.keep.is_some() {
+                    return Err(ParseError::new(start, ParseErrorKind::DuplicateKeep));
+                }
+                dice.keep = Some(keep(n));
+                continue;
+            }
+
+            match self.peek() {
+                Some(b'!') => {
+                    if dice.explode {
+                        return Err(self.error(ParseErrorKind::DuplicateExplode));
+                    }
+                    if sides == 1 {
+                        return Err(self.error(ParseErrorKind::ExplodeOneSided));
+                    }
+                    self.position += 1;
+                    dice.explode = true;
+                },
+                Some(b'r') => {
+                    self.position += 1;
+                    let face_start = self.position;
+                    let face = self.small_number()?;
+                    if !(1..=sides).contains(&face) {
+                        let kind = ParseErrorKind::RerollOutOfRange(face);
+                        return Err(ParseError::new(face_start, kind));
+                    }
+                    if !dice.reroll.contains(&face) {
+                        dice.reroll.push(face);
+                    }
+                    if dice.reroll.len() == sides as usize {
+                        let kind = ParseErrorKind::RerollEverything;
+                        return Err(ParseError::new(start, kind));
+                    }
+                },
+                _ => break,
+            }
+        }
+        Ok(Expr::Dice(dice))
+    }
+
+    /// Parse non-negative whole number.
+    fn number(&mut self) -> Result<i64, ParseError> {
+        let start = self.position;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.position += 1;
+        }
+        if start == self.position {
+            return Err(match self.peek() {
+                None => self.error(ParseErrorKind::UnexpectedEnd),
+                Some(_) => self.error(ParseErrorKind::ExpectedNumber),
+            });
+        }
+        let digits = std::str::from_utf8(&self.input[start..self.position]).unwrap();
+        digits.parse().map_err(|_| ParseError::new(start, ParseErrorKind::NumberTooLarge))
+    }
+
+    /// Parse whole number that fits into a `u32`.
+    fn small_number(&mut self) -> Result<u32, ParseError> {
+        let start = self.position;
+        let number = self.number()?;
+        u32::try_from(number)
+            .map_err(|_| ParseError::new(start, ParseErrorKind::NumberTooLarge))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<u8> {
+        self.input.get(self.position + offset).copied()
+    }
+
+    fn error(&self, kind: ParseErrorKind) -> ParseError {
+        ParseError::new(self.position, kind)
+    }
+
+    /// Error for the number just read, which ends at the current position.
+    fn error_before(&self, kind: ParseErrorKind) -> ParseError {
+        let mut position = self.position;
+        while position > 0 && self.input[position - 1].is_ascii_digit() {
+            position -= 1;
+        }
+        ParseError::new(position, kind)
+    }
+
+    /// Error for the (possibly multi-byte) character at current position.
+    fn unexpected(&self) -> ParseError {
+        let rest = std::str::from_utf8(&self.input[self.position..]).unwrap_or("");
+        match rest.chars().next() {
+            Some(c) => self.error(ParseErrorKind::UnexpectedChar(c)),
+            None => self.error(ParseErrorKind::UnexpectedEnd),
+        }
+    }
+}
+
+
+/// Canonical notation for an expression, with only the parentheses needed.
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Number(n) => write!(f, "{n}"),
+            Expr::Dice(dice) => write!(f, "{dice}"),
+            Expr::Neg(expr) => match **expr {
+                Expr::Binary(..) => write!(f, "-({expr})"),
+                _ => write!(f, "-{expr}"),
+            },
+            Expr::Binary(left, op, right) => {
+                let needs_parens = |expr: &Expr, is_right: bool| match expr {
+                    Expr::Binary(_, inner, _) => {
+                        inner.precedence() < op.precedence()
+                        || (is_right && inner.precedence() == op.precedence())
+                    },
+                    _ => false,
+                };
+                if needs_parens(left, false) {
+                    write!(f, "({left})")?;
+                } else {
+                    write!(f, "{left}")?;
+                }
+                write!(f, "{}", op.symbol())?;
+                if needs_parens(right, true) {
+                    write!(f, "({right})")
+                } else {
+                    write!(f, "{right}")
+                }
+            },
+        }
+    }
+}
+
+
+impl fmt::Display for Dice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}d{}", self.count, self.sides)?;
+        match self.keep {
+            Some(Keep::Highest(n)) => write!(f, "kh{n}")?,
+            Some(Keep::Lowest(n)) => write!(f, "kl{n}")?,
+            Some(Keep::DropHighest(n)) => write!(f, "dh{n}")?,
+            Some(Keep::DropLowest(n)) => write!(f, "dl{n}")?,
+            None => {},
+        }
+        if self.explode {
+            write!(f, "!")?;
+        }
+        for face in &self.reroll {
+            write!(f, "r{face}")?;
+        }
+        Ok(())
+    }
+}
+
+
+/// Show individual dice, eg. `4d6kh3 [6, 5, ~2, 3] = 14`.
+/// Dropped dice are marked with `~`, rerolled dice with `r`, and
+/// exploding dice with `!`.
+impl fmt::Display for DiceGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} [", self.dice)?;
+        for (i, die) in self.rolls.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{die}")?;
+        }
+        write!(f, "] = {}", self.total)
+    }
+}
+
+
+impl fmt::Display for Die {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.state {
+            DieState::Kept => {},
+            DieState::Dropped => write!(f, "~")?,
+            DieState::Rerolled => write!(f, "r")?,
+        }
+        write!(f, "{}", self.value)?;
+        if self.exploded {
+            write!(f, "!")?;
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::SeedableRng;
+    use rand::rngs::SmallRng;
+
+    fn create_roller() -> DiceRoller {
+        DiceRoller::new(SmallRng::seed_from_u64(42))
+    }
+
+    fn error_kind(input: &str) -> ParseErrorKind {
+        parse(input).unwrap_err().kind
+    }
+
+    fn dice(count: u32, sides: u32) -> Expr {
+        Expr::Dice(Dice::new(count, sides))
+    }
+
+    #[test]
+    fn parse_simple_dice() {
+        assert_eq!(parse("3d6"), Ok(dice(3, 6)));
+        assert_eq!(parse("d20"), Ok(dice(1, 20)));
+        assert_eq!(parse("2d%"), Ok(dice(2, 100)));
+        assert_eq!(parse(" 42 "), Ok(Expr::Number(42)));
+    }
+
+    #[test]
+    fn parse_modifiers() {
+        let Ok(Expr::Dice(dice)) = parse("4d6kh3") else { panic!() };
+        assert_eq!(dice.keep, Some(Keep::Highest(3)));
+
+        let Ok(Expr::Dice(dice)) = parse("2d20kl1") else { panic!() };
+        assert_eq!(dice.keep, Some(Keep::Lowest(1)));
+
+        let Ok(Expr::Dice(dice)) = parse("4d6k3") else { panic!() };
+        assert_eq!(dice.keep, Some(Keep::Highest(3)));
+
+        let Ok(Expr::Dice(dice)) = parse("5d10dl2") else { panic!() };
+        assert_eq!(dice.keep, Some(Keep::DropLowest(2)));
+
+        assert_eq!(error_kind("5d10dh1dl1"), ParseErrorKind::DuplicateKeep);
+    }
+
+    #[test]
+    fn parse_explode_and_reroll() {
+        let Ok(Expr::Dice(dice)) = parse("3d6!r1r2") else { panic!() };
+        assert!(dice.explode);
+        assert_eq!(dice.reroll, vec![1, 2]);
+        assert_eq!(dice.to_string(), "3d6!r1r2");
+    }
+
+    #[test]
+    fn parse_precedence() {
+        let expr = parse("1+2*3").unwrap();
+        let expected = Expr::Binary(
+            Box::new(Expr::Number(1)),
+            Op::Add,
+            Box::new(Expr::Binary(
+                Box::new(Expr::Number(2)),
+                Op::Mul,
+                Box::new(Expr::Number(3)),
+            )),
+        );
+        assert_eq!(expr, expected);
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let inputs = [
+            "3d6+2", "4d6kh3", "(1d8+1d6)*2", "2d20kl1+5", "-(1-2)", "1-(2-3)", "-1d4",
+        ];
+        for input in inputs {
+            let expr = parse(input).unwrap();
+            assert_eq!(expr.to_string(), input);
+            assert_eq!(parse(&expr.to_string()).unwrap(), expr);
+        }
+        assert_eq!(parse("d%").unwrap().to_string(), "1d100");
+        assert_eq!(parse("((1 + 2))").unwrap().to_string(), "1+2");
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(error_kind(""), ParseErrorKind::Empty);
+        assert_eq!(error_kind("3d"), ParseErrorKind::UnexpectedEnd);
+        assert_eq!(error_kind("3dx"), ParseErrorKind::ExpectedNumber);
+        assert_eq!(error_kind("3d0"), ParseErrorKind::BadSides(0));
+        assert_eq!(error_kind("5000d6"), ParseErrorKind::TooManyDice);
+        assert_eq!(error_kind("(1+2"), ParseErrorKind::UnclosedParen);
+        assert_eq!(error_kind("1+2)"), ParseErrorKind::UnexpectedChar(')'));
+        assert_eq!(error_kind("2d6 3"), ParseErrorKind::UnexpectedChar('3'));
+        assert_eq!(error_kind("1d1!"), ParseErrorKind::ExplodeOneSided);
+        assert_eq!(error_kind("1d6!!"), ParseErrorKind::DuplicateExplode);
+        assert_eq!(error_kind("1d6r7"), ParseErrorKind::RerollOutOfRange(7));
+        assert_eq!(error_kind("1d2r1r2"), ParseErrorKind::RerollEverything);
+        assert_eq!(error_kind("99999999999999999999"), ParseErrorKind::NumberTooLarge);
+        assert_eq!(error_kind("1d6 🦀"), ParseErrorKind::UnexpectedChar('🦀'));
+    }
+
+    #[test]
+    fn parse_error_positions() {
+        assert_eq!(parse("3d6 + 2d0").unwrap_err().position, 8);
+        assert_eq!(parse("4d6kx3").unwrap_err().position, 4);
+        assert_eq!(parse("1 + (2 * 3").unwrap_err().position, 4);
+    }
+
+    #[test]
+    fn parse_error_annotate() {
+        let input = "3d6 + 2d0";
+        let error = parse(input).unwrap_err();
+        assert_eq!(
+            error.annotate(input),
+            "3d6 + 2d0\n        ^ dice must have between 1 and 1000000 sides, not 0",
+        );
+        assert_eq!(
+            error.to_string(),
+            "dice must have between 1 and 1000000 sides, not 0 at position 8",
+        );
+
+        // As shown in the documentation of `annotate()`
+        let error = parse("4d6x").unwrap_err();
+        assert_eq!(error.annotate("4d6x"), "4d6x\n   ^ unexpected character 'x'");
+    }
+
+    #[test]
+    fn roll_arithmetic() {
+        let mut roller = create_roller();
+        let roll = parse("(2+3)*4-10/3").unwrap().roll(&mut roller).unwrap();
+        assert_eq!(roll.total, 17);
+        assert!(roll.groups.is_empty());
+    }
+
+    #[test]
+    fn roll_division_by_zero() {
+        let mut roller = create_roller();
+        let result = parse("1d6/(1d1-1)").unwrap().roll(&mut roller);
+        assert_eq!(result, Err(RollError::DivisionByZero));
+    }
+
+    #[test]
+    fn roll_records_dice() {
+        let mut roller = create_roller();
+        let roll = parse("3d6+2").unwrap().roll(&mut roller).unwrap();
+        assert_eq!(roll.groups.len(), 1);
+        let group = &roll.groups[0];
+        assert_eq!(group.rolls.len(), 3);
+        let sum: i64 = group.rolls.iter().map(|die| i64::from(die.value)).sum();
+        assert_eq!(group.total, sum);
+        assert_eq!(roll.total, sum + 2);
+    }
+
+    #[test]
+    fn roll_keep_highest() {
+        let mut roller = create_roller();
+        let expr = parse("4d6kh3").unwrap();
+        for _ in 0..100 {
+            let roll = expr.roll(&mut roller).unwrap();
+            let group = &roll.groups[0];
+            let mut values: Vec<u32> = group.rolls.iter().map(|die| die.value).collect();
+            values.sort_unstable();
+            let expected: u32 = values[1..].iter().sum();
+            assert_eq!(roll.total, i64::from(expected));
+            let dropped = group.rolls.iter().filter(|d| d.state == DieState::Dropped);
+            assert_eq!(dropped.count(), 1);
+        }
+    }
+
+    #[test]
+    fn roll_drop_lowest_and_keep_lowest() {
+        let mut roller = create_roller();
+        for _ in 0..100 {
+            let roll = parse("2d20kl1").unwrap().roll(&mut roller).unwrap();
+            let values: Vec<_> = roll.groups[0].rolls.iter().map(|d| d.value).collect();
+            assert_eq!(roll.total, i64::from(*values.iter().min().unwrap()));
+
+            let roll = parse("3d8dh1").unwrap().roll(&mut roller).unwrap();
+            let mut values: Vec<_> = roll.groups[0].rolls.iter().map(|d| d.value).collect();
+            values.sort_unstable();
+            assert_eq!(roll.total, i64::from(values[0] + values[1]));
+        }
+    }
+
+    #[test]
+    fn roll_reroll() {
+        let mut roller = create_roller();
+        let expr = parse("10d4r1r2").unwrap();
+        for _ in 0..20 {
+            let roll = expr.roll(&mut roller).unwrap();
+            for die in &roll.groups[0].rolls {
+                match die.state {
+                    DieState::Rerolled => assert!(die.value <= 2),
+                    _ => assert!(die.value >= 3),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn roll_explode() {
+        let mut roller = create_roller();
+        let expr = parse("20d2!").unwrap();
+        let roll = expr.roll(&mut roller).unwrap();
+        let rolls = &roll.groups[0].rolls;
+
+        // Every two explodes into another die, so there are more than 20
+        let exploded = rolls.iter().filter(|d| d.exploded).count();
+        assert!(exploded > 0);
+        assert_eq!(rolls.len(), 20 + exploded);
+        assert!(rolls.iter().all(|d| d.exploded == (d.value == 2)));
+    }
+
+    #[test]
+    fn display_group() {
+        let group = DiceGroup {
+            dice: Dice { keep: Some(Keep::Highest(3)), explode: true, ..Dice::new(4, 6) },
+            rolls: vec![
+                Die { value: 6, state: DieState::Kept, exploded: true },
+                Die { value: 1, state: DieState::Rerolled, exploded: false },
+                Die { value: 5, state: DieState::Kept, exploded: false },
+                Die { value: 2, state: DieState::Dropped, exploded: false },
+                Die { value: 3, state: DieState::Kept, exploded: false },
+            ],
+            total: 14,
+        };
+        assert_eq!(group.to_string(), "4d6kh3! [6!, r1, 5, ~2, 3] = 14");
+    }
+}
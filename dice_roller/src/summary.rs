@@ -0,0 +1,75 @@
+//! Statistics over the totals of many rolls.
+
+
+/// Count, range, and spread of a set of roll totals.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Summary {
+    pub count: usize,
+    pub min: i64,
+    pub max: i64,
+    pub mean: f64,
+    pub median: f64,
+
+    /// Population standard deviation.
+    pub std_dev: f64,
+}
+
+
+impl Summary {
+    /// Summarise the given totals, or `None` if there aren't any.
+    pub fn new(totals: &[i64]) -> Option<Self> {
+        let mut sorted = totals.to_vec();
+        sorted.sort_unstable();
+        let count = sorted.len();
+        let min = *sorted.first()?;
+        let max = *sorted.last()?;
+        let mean = sorted.iter().map(|&total| total as f64).sum::<f64>() / count as f64;
+        let variance = sorted
+            .iter()
+            .map(|&total| (total as f64 - mean).powi(2))
+            .sum::<f64>() / count as f64;
+        let median = if count % 2 == 1 {
+            sorted[count / 2] as f64
+        } else {
+            // Added as floats, as the totals' sum may not fit in an `i64`
+            (sorted[count / 2 - 1] as f64 + sorted[count / 2] as f64) / 2.0
+        };
+        Some(Summary { count, min, max, mean, median, std_dev: variance.sqrt() })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty() {
+        assert_eq!(Summary::new(&[]), None);
+    }
+
+    #[test]
+    fn odd_count() {
+        let summary = Summary::new(&[9, 2, 4]).unwrap();
+        assert_eq!((summary.count, summary.min, summary.max), (3, 2, 9));
+        assert_eq!(summary.mean, 5.0);
+        assert_eq!(summary.median, 4.0);
+    }
+
+    #[test]
+    fn even_count() {
+        let summary = Summary::new(&[2, 4, 4, 4, 5, 5, 7, 9]).unwrap();
+        assert_eq!(summary.mean, 5.0);
+        assert_eq!(summary.median, 4.5);
+        assert_eq!(summary.std_dev, 2.0);
+    }
+
+    #[test]
+    fn extreme_totals() {
+        let summary = Summary::new(&[i64::MAX, i64::MAX]).unwrap();
+        assert_eq!(summary.median, i64::MAX as f64);
+        assert_eq!(summary.std_dev, 0.0);
+        let summary = Summary::new(&[i64::MIN, i64::MIN, 0, 0]).unwrap();
+        assert_eq!(summary.median, i64::MIN as f64 / 2.0);
+    }
+}
@@ -0,0 +1,380 @@
+//! Generic signed fixed-point numbers.
+
+use std::fmt::Debug;
+use std::hash::Hash;
+
+
+/// Signed integer types that can hold the bits of a fixed-point number.
+///
+/// Implemented for `i8`, `i16`, `i32`, and `i64`. Every value fits in an
+/// `i128`, which leaves room to widen products and quotients.
+pub trait Storage: Copy + Debug + Default + Eq + Hash + Ord + sealed::Sealed {
+    const BITS: u32;
+    const MIN: Self;
+    const MAX: Self;
+    const ZERO: Self;
+
+    fn to_i128(self) -> i128;
+
+    /// `None` if the value doesn't fit.
+    fn checked_from_i128(value: i128) -> Option<Self>;
+
+    /// Keep only the low bits, as `as` does.
+    fn wrapping_from_i128(value: i128) -> Self;
+}
+
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+
+macro_rules! storage {
+    ($($int:ty),*) => {$(
+        impl sealed::Sealed for $int {}
+
+        impl Storage for $int {
+            const BITS: u32 = <$int>::BITS;
+            const MIN: Self = <$int>::MIN;
+            const MAX: Self = <$int>::MAX;
+            const ZERO: Self = 0;
+
+            fn to_i128(self) -> i128 {
+                i128::from(self)
+            }
+
+            fn checked_from_i128(value: i128) -> Option<Self> {
+                <$int>::try_from(value).ok()
+            }
+
+            fn wrapping_from_i128(value: i128) -> Self {
+                value as $int
+            }
+        }
+    )*};
+}
+
+storage!(i8, i16, i32, i64);
+
+
+/// How to round values falling between two representable numbers.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum Rounding {
+    /// To the nearest, with ties away from zero.
+    #[default]
+    Nearest,
+
+    /// To the nearest, with ties to the even number, as IEEE 754 does.
+    NearestEven,
+
+    /// Toward zero, dropping the extra bits, as `as` casts do.
+    TowardZero,
+
+    /// Toward negative infinity.
+    Down,
+
+    /// Toward positive infinity.
+    Up,
+}
+
+
+impl Rounding {
+    /// Round a float to a whole number.
+    pub fn round(self, value: f64) -> f64 {
+        match self {
+            Rounding::Nearest => value.round(),
+            Rounding::NearestEven => value.round_ties_even(),
+            Rounding::TowardZero => value.trunc(),
+            Rounding::Down => value.floor(),
+            Rounding::Up => value.ceil(),
+        }
+    }
+
+    /// Divide, rounding the quotient to a whole number.
+    ///
+    /// Panics if `divisor` is zero.
+    pub fn divide(self, dividend: i128, divisor: i128) -> i128 {
+        let quotient = dividend / divisor;
+        let remainder = dividend % divisor;
+        if remainder == 0 {
+            return quotient;
+        }
+        // Direction away from zero, for the exact quotient
+        let away = if (dividend < 0) == (divisor < 0) { 1 } else { -1 };
+        let round_away = match self {
+            Rounding::TowardZero => false,
+            Rounding::Down => away < 0,
+            Rounding::Up => away > 0,
+            Rounding::Nearest | Rounding::NearestEven => {
+                let twice = remainder.unsigned_abs() * 2;
+                let divisor = divisor.unsigned_abs();
+                twice > divisor
+                    || (twice == divisor && (self == Rounding::Nearest || quotient % 2 != 0))
+            },
+        };
+        if round_away { quotient + away } else { quotient }
+    }
+}
+
+
+/// Signed fixed-point number, stored in the integer `I` with `FRAC` of its
+/// bits after the binary point.
+///
+/// In Q notation, `Fixed<i32, 16>` is Q16.16, with 16 bits before the binary
+/// point, including the sign, and 16 after. `FRAC` must be less than the
+/// bits in `I`, leaving room for the sign.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Fixed<I: Storage, const FRAC: u32>(I);
+
+
+impl<I: Storage, const FRAC: u32> Fixed<I, FRAC> {
+    /// Stops the type being used with too many fractional bits.
+    const VALID: () = assert!(FRAC < I::BITS, "FRAC must be less than the bits in storage");
+
+    /// Smallest difference between two numbers, `2^-FRAC`.
+    pub const EPSILON: f64 = power_of_two(-(FRAC as i32));
+
+    pub const MIN: Self = Self::from_bits(I::MIN);
+    pub const MAX: Self = Self::from_bits(I::MAX);
+    pub const ZERO: Self = Self::from_bits(I::ZERO);
+
+    /// Number with the given underlying bits, ie. `bits * 2^-FRAC`.
+    pub const fn from_bits(bits: I) -> Self {
+        let () = Self::VALID;
+        Fixed(bits)
+    }
+
+    /// Underlying bits of the number.
+    pub const fn to_bits(self) -> I {
+        self.0
+    }
+
+    /// Convert from a float, or `None` if it's out of range or NaN.
+    pub fn checked_from_f64(value: f64, rounding: Rounding) -> Option<Self> {
+        let scaled = rounding.round(value * power_of_two(FRAC as i32));
+        let limit = power_of_two(I::BITS as i32 - 1);
+        if scaled >= -limit && scaled < limit {
+            I::checked_from_i128(scaled as i128).map(Self::from_bits)
+        } else {
+            None
+        }
+    }
+
+    /// Convert from a float, clamping it to the range of the type. NaN
+    /// becomes zero.
+    pub fn saturating_from_f64(value: f64, rounding: Rounding) -> Self {
+        if value.is_nan() {
+            return Self::ZERO;
+        }
+        Self::checked_from_f64(value, rounding)
+            .unwrap_or(if value < 0.0 { Self::MIN } else { Self::MAX })
+    }
+
+    /// Convert from a float, or `None` if it's out of range or NaN.
+    pub fn checked_from_f32(value: f32, rounding: Rounding) -> Option<Self> {
+        Self::checked_from_f64(f64::from(value), rounding)
+    }
+
+    /// Convert from a float, clamping it to the range of the type. NaN
+    /// becomes zero.
+    pub fn saturating_from_f32(value: f32, rounding: Rounding) -> Self {
+        Self::saturating_from_f64(f64::from(value), rounding)
+    }
+
+    /// Convert from a whole number, or `None` if it's out of range.
+    pub fn checked_from_int(value: i64) -> Option<Self> {
+        Self::from_i128(i128::from(value) << FRAC)
+    }
+
+    /// Convert from a whole number, clamping it to the range of the type.
+    pub fn saturating_from_int(value: i64) -> Self {
+        Self::saturating_from_i128(i128::from(value) << FRAC)
+    }
+
+    /// Nearest `f64`, which is exact unless there are more than 53
+    /// significant bits.
+    pub fn to_f64(self) -> f64 {
+        self.0.to_i128() as f64 * Self::EPSILON
+    }
+
+    /// Nearest `f32`, which is exact unless there are more than 24
+    /// significant bits.
+    pub fn to_f32(self) -> f32 {
+        // Via an integer, as going through f64 could round twice
+        (self.0.to_i128() as f32) * power_of_two(-(FRAC as i32)) as f32
+    }
+
+    /// Number from bits widened to an `i128`, or `None` if they don't fit.
+    pub(crate) fn from_i128(bits: i128) -> Option<Self> {
+        I::checked_from_i128(bits).map(Self::from_bits)
+    }
+
+    /// Number from bits widened to an `i128`, clamped to the range.
+    pub(crate) fn saturating_from_i128(bits: i128) -> Self {
+        Self::from_i128(bits).unwrap_or(if bits < 0 { Self::MIN } else { Self::MAX })
+    }
+
+    /// Number from the low bits of an `i128`, wrapping around the range.
+    pub(crate) fn wrapping_from_i128(bits: i128) -> Self {
+        Self::from_bits(I::wrapping_from_i128(bits))
+    }
+
+    /// Underlying bits, widened to an `i128`.
+    pub(crate) fn wide_bits(self) -> i128 {
+        self.0.to_i128()
+    }
+}
+
+
+/// Saturates, rounding to nearest.
+impl<I: Storage, const FRAC: u32> From<f64> for Fixed<I, FRAC> {
+    fn from(value: f64) -> Self {
+        Self::saturating_from_f64(value, Rounding::Nearest)
+    }
+}
+
+/// Saturates, rounding to nearest.
+impl<I: Storage, const FRAC: u32> From<f32> for Fixed<I, FRAC> {
+    fn from(value: f32) -> Self {
+        Self::saturating_from_f32(value, Rounding::Nearest)
+    }
+}
+
+impl<I: Storage, const FRAC: u32> From<Fixed<I, FRAC>> for f64 {
+    fn from(value: Fixed<I, FRAC>) -> f64 {
+        value.to_f64()
+    }
+}
+
+impl<I: Storage, const FRAC: u32> From<Fixed<I, FRAC>> for f32 {
+    fn from(value: Fixed<I, FRAC>) -> f32 {
+        value.to_f32()
+    }
+}
+
+
+/// Exact power of two, for exponents within the normal range of `f64`.
+const fn power_of_two(exponent: i32) -> f64 {
+    f64::from_bits(((1023 + exponent) as u64) << 52)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{Q15, Q16_16, Q31, Q32_32, Q7};
+
+    #[test]
+    fn ranges() {
+        assert_eq!(f64::from(Q7::MIN), -1.0);
+        assert_eq!(f64::from(Q7::MAX), 127.0 / 128.0);
+        assert_eq!(f64::from(Q15::MAX), 1.0 - Q15::EPSILON);
+        assert_eq!(f64::from(Q16_16::MIN), -32768.0);
+        assert_eq!(f64::from(Q16_16::MAX), 32768.0 - Q16_16::EPSILON);
+        assert_eq!(Q31::EPSILON, 2f64.powi(-31));
+        assert_eq!(Q32_32::EPSILON, 2f64.powi(-32));
+        assert_eq!(f64::from(Q32_32::MIN), -2f64.powi(31));
+        assert_eq!(Fixed::<i64, 63>::EPSILON, 2f64.powi(-63));
+    }
+
+    #[test]
+    fn rounding_modes() {
+        // Exactly halfway between 2 and 3 EPSILON, then a little either side
+        let half = 2.5 * Q7::EPSILON;
+        let cases = [
+            (Rounding::Nearest, [3, 2, 3, -3, -2, -3]),
+            (Rounding::NearestEven, [2, 2, 3, -2, -2, -3]),
+            (Rounding::TowardZero, [2, 2, 2, -2, -2, -2]),
+            (Rounding::Down, [2, 2, 2, -3, -3, -3]),
+            (Rounding::Up, [3, 3, 3, -2, -2, -2]),
+        ];
+        for (rounding, expected) in cases {
+            let values = [half, half - 1e-6, half + 1e-6, -half, -half + 1e-6, -half - 1e-6];
+            let bits: Vec<i8> = values
+                .iter()
+                .map(|&value| Q7::checked_from_f64(value, rounding).unwrap().to_bits())
+                .collect();
+            assert_eq!(bits, expected, "{rounding:?}");
+        }
+    }
+
+    #[test]
+    fn integer_rounding_matches_float() {
+        let modes = [
+            Rounding::Nearest,
+            Rounding::NearestEven,
+            Rounding::TowardZero,
+            Rounding::Down,
+            Rounding::Up,
+        ];
+        for rounding in modes {
+            for dividend in -40..=40 {
+                for divisor in [-8, -5, -4, -3, -2, -1, 1, 2, 3, 4, 5, 8] {
+                    let expected = rounding.round(f64::from(dividend) / f64::from(divisor));
+                    let actual = rounding.divide(i128::from(dividend), i128::from(divisor));
+                    assert_eq!(actual as f64, expected, "{rounding:?} {dividend}/{divisor}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn checked_from_f64() {
+        assert_eq!(Q7::checked_from_f64(-1.0, Rounding::Nearest), Some(Q7::MIN));
+        assert_eq!(Q7::checked_from_f64(1.0, Rounding::Nearest), None);
+        assert_eq!(Q7::checked_from_f64(0.999, Rounding::Nearest), None);
+        assert_eq!(Q7::checked_from_f64(0.999, Rounding::Down), Some(Q7::MAX));
+        assert_eq!(Q7::checked_from_f64(-1.001, Rounding::Up), Some(Q7::MIN));
+        assert_eq!(Q7::checked_from_f64(f64::NAN, Rounding::Nearest), None);
+        assert_eq!(Q7::checked_from_f64(f64::INFINITY, Rounding::Nearest), None);
+
+        // Largest magnitudes for 64-bit storage, where f64 is inexact
+        let max = Q32_32::checked_from_f64(2f64.powi(31), Rounding::Nearest);
+        assert_eq!(max, None);
+        let min = Q32_32::checked_from_f64(-2f64.powi(31), Rounding::Nearest);
+        assert_eq!(min, Some(Q32_32::MIN));
+    }
+
+    #[test]
+    fn saturating_from_f64() {
+        assert_eq!(Q7::saturating_from_f64(1.0, Rounding::Nearest), Q7::MAX);
+        assert_eq!(Q7::saturating_from_f64(-7.0, Rounding::Nearest), Q7::MIN);
+        assert_eq!(Q7::saturating_from_f64(f64::NEG_INFINITY, Rounding::Nearest), Q7::MIN);
+        assert_eq!(Q7::saturating_from_f64(f64::NAN, Rounding::Nearest), Q7::ZERO);
+        assert_eq!(Q15::saturating_from_f32(0.25, Rounding::Nearest).to_bits(), 1 << 13);
+        assert_eq!(Q16_16::from(1e10), Q16_16::MAX);
+        assert_eq!(Q16_16::from(-1.5_f32).to_bits(), -3 << 15);
+    }
+
+    #[test]
+    fn from_int() {
+        assert_eq!(Q16_16::checked_from_int(-3).unwrap().to_bits(), -3 << 16);
+        assert_eq!(Q16_16::checked_from_int(32767).map(f64::from), Some(32767.0));
+        assert_eq!(Q16_16::checked_from_int(32768), None);
+        assert_eq!(Q16_16::checked_from_int(-32768), Some(Q16_16::MIN));
+        assert_eq!(Q7::checked_from_int(-1), Some(Q7::MIN));
+        assert_eq!(Q7::saturating_from_int(1), Q7::MAX);
+        assert_eq!(Q32_32::saturating_from_int(i64::MIN), Q32_32::MIN);
+    }
+
+    #[test]
+    fn to_floats() {
+        assert_eq!(Q31::from_bits(i32::MIN).to_f32(), -1.0);
+        assert_eq!(Q31::from_bits(1).to_f64(), 2f64.powi(-31));
+        assert_eq!(Q32_32::from_bits(-3 << 31).to_f64(), -1.5);
+        assert_eq!(f32::from(Q15::from_bits(-1 << 14)), -0.5);
+
+        // Every Q15 survives the trip through f32
+        for bits in i16::MIN..=i16::MAX {
+            let q = Q15::from_bits(bits);
+            assert_eq!(Q15::checked_from_f32(q.to_f32(), Rounding::Nearest), Some(q));
+        }
+    }
+
+    #[test]
+    fn ordering() {
+        assert!(Q7::MIN < Q7::ZERO && Q7::ZERO < Q7::MAX);
+        assert!(Q16_16::from(-0.5) < Q16_16::from(0.25));
+    }
+}
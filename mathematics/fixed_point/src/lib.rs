@@ -1,48 +1,34 @@
+//! Signed fixed-point numbers, generic over their storage and the position
+//! of the binary point.
+//!
+//! Aliases are given for common formats in Q notation, where Qm.n has m
+//! integer bits, counting the sign, and n fractional bits. Formats with
+//! only a sign bit before the binary point give just the fractional bits,
+//! eg. Q7 for an `i8` holding numbers from -1 up to but not including 1.
 
-/// Q7 fixed-point format, as seen in *Rust in Action*.
-/// Can represent numbers in inclusive range [1.0, -1.0]
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-pub struct Q7(i8);
-
+mod fixed;
+mod ops;
+mod text;
 
-impl Q7 {
-    pub const EPSILON: f64 = 0.0078125;
-}
+pub use fixed::{Fixed, Rounding, Storage};
+pub use text::ParseFixedError;
 
 
-impl From<f32> for Q7 {
-    fn from(f: f32) -> Q7 {
-        Q7::from(f as f64)
-    }
-}
+/// Q7 fixed-point format, as seen in *Rust in Action*.
+/// Can represent numbers in range [-1.0, 1.0)
+pub type Q7 = Fixed<i8, 7>;
 
-impl From<f64> for Q7 {
-    fn from(f: f64) -> Self {
-        debug_assert!(f >= -1.0);
-        debug_assert!(f <= 1.0);
-        if f >= 1.0 {
-            Q7(127)
-        } else if f <= -1.0{
-            Q7(-128)
-        } else {
-            Q7((f * 128.0) as i8)
-        }
-    }
-}
+/// Q15, from -1 up to 1, as used for 16-bit audio.
+pub type Q15 = Fixed<i16, 15>;
 
-impl From<Q7> for f32 {
-    fn from(q: Q7) -> f32 {
-        f64::from(q) as f32
-    }
-}
+/// Q31, from -1 up to 1.
+pub type Q31 = Fixed<i32, 31>;
 
+/// Q16.16, from -32768 up to 32768, with 16 bits after the binary point.
+pub type Q16_16 = Fixed<i32, 16>;
 
-impl From<Q7> for f64 {
-    fn from(q: Q7) -> f64 {
-        (q.0 as f64) * 2f64.powf(-7.0)
-        //~ (q.0) as f64 * Q7::EPSILON
-    }
-}
+/// Q32.32, with 32 bits either side of the binary point.
+pub type Q32_32 = Fixed<i64, 32>;
 
 
 #[cfg(test)]
@@ -63,21 +49,36 @@ mod tests
     /// Peek behind curtains
     #[test]
     fn q7_from_f64_internals() {
-        assert_eq!(Q7::from(0.0), Q7(0));
-        assert_eq!(Q7::from(0.5), Q7(64));
-        assert_eq!(Q7::from(-0.5), Q7(-64));
-        assert_eq!(Q7::from(1.0), Q7(127));
-        assert_eq!(Q7::from(-1.0), Q7(-128));
+        assert_eq!(Q7::from(0.0), Q7::from_bits(0));
+        assert_eq!(Q7::from(0.5), Q7::from_bits(64));
+        assert_eq!(Q7::from(-0.5), Q7::from_bits(-64));
+        assert_eq!(Q7::from(1.0), Q7::from_bits(127));
+        assert_eq!(Q7::from(-1.0), Q7::from_bits(-128));
     }
 
     #[test]
     fn f64_from_q7_internals() {
-        assert_eq!(f64::from(Q7(-128)), -1.0);
-        assert_eq!(f64::from(Q7(-64)), -0.5);
-        assert_eq!(f64::from(Q7(0)), 0.0);
-        assert_eq!(f64::from(Q7(64)), 0.5);
+        assert_eq!(f64::from(Q7::from_bits(-128)), -1.0);
+        assert_eq!(f64::from(Q7::from_bits(-64)), -0.5);
+        assert_eq!(f64::from(Q7::from_bits(0)), 0.0);
+        assert_eq!(f64::from(Q7::from_bits(64)), 0.5);
+
+        // Note that the largest Q7 falls one EPSILON short of one
+        assert!(f64::from(Q7::from_bits(127)) + Q7::EPSILON == 1.0);
+    }
 
-        // Note that the
-        assert!(f64::from(Q7(127)) + Q7::EPSILON == 1.0);
+    /// Out of range values saturate, in debug and release builds alike
+    #[test]
+    fn q7_from_f64_saturates() {
+        assert_eq!(Q7::from(3.0), Q7::MAX);
+        assert_eq!(Q7::from(-3.0_f32), Q7::MIN);
+    }
+
+    /// Rounds to nearest, rather than truncating
+    #[test]
+    fn q7_from_f64_rounds() {
+        assert_eq!(Q7::from(0.9 * Q7::EPSILON).to_bits(), 1);
+        assert_eq!(Q7::from(-0.9 * Q7::EPSILON).to_bits(), -1);
+        assert_eq!(Q7::saturating_from_f64(0.9 * Q7::EPSILON, Rounding::TowardZero).to_bits(), 0);
     }
 }
@@ -1,5 +1,5 @@
 
-use fixed_point::Q7;
+use fixed_point::{Rounding, Q16_16, Q7};
 
 
 fn main() {
@@ -12,4 +12,16 @@ fn main() {
     println!("Minus half back to f64 is {:?}", f64::from(minus_half));
 
     println!("Smallest difference is {}", Q7::EPSILON);
+
+    let two_thirds = Q16_16::from(2.0 / 3.0);
+    println!("Two thirds in Q16.16 is {:?}, or {}", two_thirds, f64::from(two_thirds));
+    let truncated = Q16_16::saturating_from_f64(2.0 / 3.0, Rounding::TowardZero);
+    println!("Truncated, it's {:?}, or {}", truncated, f64::from(truncated));
+
+    let quarter: Q7 = "0.25".parse().expect("a quarter fits in Q7");
+    println!("Half times a quarter is {}", half * quarter);
+    println!("Half plus half saturates to {}", half.saturating_add(half));
+    println!("Half plus half wraps to {}", half.wrapping_add(half));
+    println!("Half plus half checked is {:?}", half.checked_add(half));
+    println!("Two thirds to three places is {:.3}", two_thirds);
 }
@@ -0,0 +1,341 @@
+//! Arithmetic on fixed-point numbers.
+//!
+//! Results are worked out exactly in an `i128`, then narrowed back to the
+//! storage type, so products and quotients are rounded only once. Each
+//! operation comes in three flavours for results out of range: checked,
+//! returning `None`, saturating at the limits, and wrapping around them as
+//! integers do. The operators themselves panic on overflow, in debug and
+//! release builds alike.
+
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+use crate::{Fixed, Rounding, Storage};
+
+
+impl<I: Storage, const FRAC: u32> Fixed<I, FRAC> {
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        Self::from_i128(self.wide_bits() + rhs.wide_bits())
+    }
+
+    pub fn saturating_add(self, rhs: Self) -> Self {
+        Self::saturating_from_i128(self.wide_bits() + rhs.wide_bits())
+    }
+
+    pub fn wrapping_add(self, rhs: Self) -> Self {
+        Self::wrapping_from_i128(self.wide_bits() + rhs.wide_bits())
+    }
+
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        Self::from_i128(self.wide_bits() - rhs.wide_bits())
+    }
+
+    pub fn saturating_sub(self, rhs: Self) -> Self {
+        Self::saturating_from_i128(self.wide_bits() - rhs.wide_bits())
+    }
+
+    pub fn wrapping_sub(self, rhs: Self) -> Self {
+        Self::wrapping_from_i128(self.wide_bits() - rhs.wide_bits())
+    }
+
+    /// Negation, or `None` for `MIN`, which has no positive counterpart.
+    pub fn checked_neg(self) -> Option<Self> {
+        Self::from_i128(-self.wide_bits())
+    }
+
+    pub fn saturating_neg(self) -> Self {
+        Self::saturating_from_i128(-self.wide_bits())
+    }
+
+    pub fn wrapping_neg(self) -> Self {
+        Self::wrapping_from_i128(-self.wide_bits())
+    }
+
+    /// Product, rounded to nearest.
+    pub fn checked_mul(self, rhs: Self) -> Option<Self> {
+        self.checked_mul_with(rhs, Rounding::Nearest)
+    }
+
+    /// Product, rounded as given.
+    pub fn checked_mul_with(self, rhs: Self, rounding: Rounding) -> Option<Self> {
+        Self::from_i128(self.wide_mul(rhs, rounding))
+    }
+
+    /// Product, rounded to nearest.
+    pub fn saturating_mul(self, rhs: Self) -> Self {
+        Self::saturating_from_i128(self.wide_mul(rhs, Rounding::Nearest))
+    }
+
+    /// Product, rounded to nearest.
+    pub fn wrapping_mul(self, rhs: Self) -> Self {
+        Self::wrapping_from_i128(self.wide_mul(rhs, Rounding::Nearest))
+    }
+
+    /// Quotient, rounded to nearest, or `None` when dividing by zero.
+    pub fn checked_div(self, rhs: Self) -> Option<Self> {
+        self.checked_div_with(rhs, Rounding::Nearest)
+    }
+
+    /// Quotient, rounded as given, or `None` when dividing by zero.
+    pub fn checked_div_with(self, rhs: Self, rounding: Rounding) -> Option<Self> {
+        Self::from_i128(self.wide_div(rhs, rounding)?)
+    }
+
+    /// Quotient, rounded to nearest.
+    ///
+    /// Panics when dividing by zero.
+    pub fn saturating_div(self, rhs: Self) -> Self {
+        Self::saturating_from_i128(self.wide_div(rhs, Rounding::Nearest).expect(DIVIDE_BY_ZERO))
+    }
+
+    /// Quotient, rounded to nearest.
+    ///
+    /// Panics when dividing by zero.
+    pub fn wrapping_div(self, rhs: Self) -> Self {
+        Self::wrapping_from_i128(self.wide_div(rhs, Rounding::Nearest).expect(DIVIDE_BY_ZERO))
+    }
+
+    /// Exact product, rounded back to `FRAC` fractional bits.
+    fn wide_mul(self, rhs: Self, rounding: Rounding) -> i128 {
+        rounding.divide(self.wide_bits() * rhs.wide_bits(), 1 << FRAC)
+    }
+
+    /// Quotient with `FRAC` fractional bits, or `None` for division by zero.
+    fn wide_div(self, rhs: Self, rounding: Rounding) -> Option<i128> {
+        let divisor = rhs.wide_bits();
+        (divisor != 0).then(|| rounding.divide(self.wide_bits() << FRAC, divisor))
+    }
+}
+
+
+const DIVIDE_BY_ZERO: &str = "attempt to divide by zero";
+
+
+macro_rules! operator {
+    (
+        $trait:ident, $method:ident,
+        $assign_trait:ident, $assign_method:ident,
+        $checked:ident, $verb:literal
+    ) => {
+        impl<I: Storage, const FRAC: u32> $trait for Fixed<I, FRAC> {
+            type Output = Self;
+
+            fn $method(self, rhs: Self) -> Self {
+                self.$checked(rhs).expect(concat!("attempt to ", $verb, " with overflow"))
+            }
+        }
+
+        impl<I: Storage, const FRAC: u32> $assign_trait for Fixed<I, FRAC> {
+            fn $assign_method(&mut self, rhs: Self) {
+                *self = $trait::$method(*self, rhs);
+            }
+        }
+    };
+}
+
+operator!(Add, add, AddAssign, add_assign, checked_add, "add");
+operator!(Sub, sub, SubAssign, sub_assign, checked_sub, "subtract");
+operator!(Mul, mul, MulAssign, mul_assign, checked_mul, "multiply");
+
+
+impl<I: Storage, const FRAC: u32> Div for Fixed<I, FRAC> {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self {
+        let quotient = self.wide_div(rhs, Rounding::Nearest).expect(DIVIDE_BY_ZERO);
+        Self::from_i128(quotient).expect("attempt to divide with overflow")
+    }
+}
+
+impl<I: Storage, const FRAC: u32> DivAssign for Fixed<I, FRAC> {
+    fn div_assign(&mut self, rhs: Self) {
+        *self = *self / rhs;
+    }
+}
+
+
+impl<I: Storage, const FRAC: u32> Neg for Fixed<I, FRAC> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self.checked_neg().expect("attempt to negate with overflow")
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{Q15, Q16_16, Q32_32, Q7};
+
+    /// Every pair of Q7 values.
+    fn all_pairs() -> impl Iterator<Item=(Q7, Q7)> {
+        (i8::MIN..=i8::MAX).flat_map(|a| {
+            (i8::MIN..=i8::MAX).map(move |b| (Q7::from_bits(a), Q7::from_bits(b)))
+        })
+    }
+
+    /// Compare one operation on every pair of Q7 values with the f64
+    /// reference, which is exact for sums, differences, and products, and
+    /// near enough for quotients that rounding it gives the same answer.
+    fn check_exhaustively(
+        name: &str,
+        reference: impl Fn(f64, f64) -> Option<f64>,
+        checked: impl Fn(Q7, Q7) -> Option<Q7>,
+        saturating: impl Fn(Q7, Q7) -> Q7,
+        wrapping: impl Fn(Q7, Q7) -> Q7,
+    ) {
+        for (a, b) in all_pairs() {
+            let Some(exact) = reference(a.to_f64(), b.to_f64()) else {
+                assert_eq!(checked(a, b), None, "{a:?} {name} {b:?}");
+                continue;
+            };
+            let expected = Q7::checked_from_f64(exact, Rounding::Nearest);
+            assert_eq!(checked(a, b), expected, "{a:?} {name} {b:?}");
+            if let Some(result) = expected {
+                assert!((result.to_f64() - exact).abs() <= Q7::EPSILON / 2.0, "{a:?} {name} {b:?}");
+            }
+            assert_eq!(
+                saturating(a, b),
+                Q7::saturating_from_f64(exact, Rounding::Nearest),
+                "{a:?} {name} {b:?}",
+            );
+            let bits = Rounding::Nearest.round(exact * 128.0) as i64;
+            assert_eq!(wrapping(a, b).to_bits(), bits as i8, "{a:?} {name} {b:?}");
+        }
+    }
+
+    #[test]
+    fn q7_add_exhaustive() {
+        check_exhaustively(
+            "+",
+            |a, b| Some(a + b),
+            Q7::checked_add,
+            Q7::saturating_add,
+            Q7::wrapping_add,
+        );
+    }
+
+    #[test]
+    fn q7_sub_exhaustive() {
+        check_exhaustively(
+            "-",
+            |a, b| Some(a - b),
+            Q7::checked_sub,
+            Q7::saturating_sub,
+            Q7::wrapping_sub,
+        );
+    }
+
+    #[test]
+    fn q7_mul_exhaustive() {
+        check_exhaustively(
+            "*",
+            |a, b| Some(a * b),
+            Q7::checked_mul,
+            Q7::saturating_mul,
+            Q7::wrapping_mul,
+        );
+    }
+
+    #[test]
+    fn q7_div_exhaustive() {
+        check_exhaustively(
+            "/",
+            |a, b| (b != 0.0).then_some(a / b),
+            Q7::checked_div,
+            |a, b| if b == Q7::ZERO { Q7::ZERO } else { a.saturating_div(b) },
+            |a, b| if b == Q7::ZERO { Q7::ZERO } else { a.wrapping_div(b) },
+        );
+    }
+
+    #[test]
+    fn q7_neg_exhaustive() {
+        for bits in i8::MIN..=i8::MAX {
+            let q = Q7::from_bits(bits);
+            let expected = Q7::checked_from_f64(-q.to_f64(), Rounding::Nearest);
+            assert_eq!(q.checked_neg(), expected);
+            assert_eq!(q.saturating_neg(), Q7::saturating_from_f64(-q.to_f64(), Rounding::Nearest));
+            assert_eq!(q.wrapping_neg().to_bits(), bits.wrapping_neg());
+            if bits != i8::MIN {
+                assert_eq!(-q, expected.unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn mul_rounding() {
+        // Three quarters of an EPSILON, then a quarter, each way
+        let half = Q7::from(0.5);
+        let three = Q7::from_bits(3);
+        let minus_one = Q7::from_bits(-1);
+        let quarter = Q7::from(0.25);
+        assert_eq!((half * three).to_bits(), 2);
+        assert_eq!(half.checked_mul_with(three, Rounding::NearestEven).unwrap().to_bits(), 2);
+        assert_eq!(half.checked_mul_with(three, Rounding::TowardZero).unwrap().to_bits(), 1);
+        assert_eq!((quarter * three).to_bits(), 1);
+        assert_eq!((quarter * minus_one).to_bits(), 0);
+        assert_eq!(quarter.checked_mul_with(minus_one, Rounding::Down).unwrap().to_bits(), -1);
+
+        // Exactly halfway
+        let ties = Q7::from_bits(5);
+        assert_eq!((half * ties).to_bits(), 3);
+        assert_eq!(half.checked_mul_with(ties, Rounding::NearestEven).unwrap().to_bits(), 2);
+    }
+
+    #[test]
+    fn div_rounding() {
+        let one_third = Q7::from(0.25).checked_div_with(Q7::from(0.75), Rounding::Down).unwrap();
+        assert_eq!(one_third.to_bits(), 42);
+        assert_eq!((Q7::from(0.25) / Q7::from(0.75)).to_bits(), 43);
+        assert_eq!((Q7::from(-0.25) / Q7::from(0.75)).to_bits(), -43);
+    }
+
+    #[test]
+    fn operators() {
+        let mut x = Q16_16::from(1.5);
+        x += Q16_16::from(2.25);
+        assert_eq!(x.to_f64(), 3.75);
+        x -= Q16_16::from(0.75);
+        assert_eq!(x.to_f64(), 3.0);
+        x *= Q16_16::from(-2.5);
+        assert_eq!(x.to_f64(), -7.5);
+        x /= Q16_16::from(0.5);
+        assert_eq!(x.to_f64(), -15.0);
+        assert_eq!((-x).to_f64(), 15.0);
+        assert_eq!((Q15::from(0.5) * Q15::from(0.5)).to_f64(), 0.25);
+    }
+
+    #[test]
+    fn wide_formats() {
+        // Products and quotients need more than 64 bits along the way
+        let big = Q32_32::checked_from_int(1 << 20).unwrap();
+        let small = Q32_32::from_bits(1 << 24);
+        assert_eq!((big * small).to_f64(), 4096.0);
+        assert_eq!((small / big).to_bits(), 1 << 4);
+        assert_eq!(big.checked_mul(big), None);
+        assert_eq!(big.saturating_mul(big), Q32_32::MAX);
+        assert_eq!(big.saturating_mul(-big), Q32_32::MIN);
+
+        type Q63 = Fixed<i64, 63>;
+        let half = Q63::from(0.5);
+        assert_eq!(half * half, Q63::from(0.25));
+        assert_eq!(Q63::from(0.25) / half, half);
+        assert_eq!(Q63::MIN.saturating_mul(Q63::MIN), Q63::MAX);
+        assert_eq!(Q63::MIN.checked_mul(Q63::MIN), None);
+        assert_eq!(Q63::MIN.checked_div(Q63::MIN), None);
+    }
+
+    #[test]
+    fn division_by_zero() {
+        assert_eq!(Q7::MAX.checked_div(Q7::ZERO), None);
+        assert!(std::panic::catch_unwind(|| Q7::MAX / Q7::ZERO).is_err());
+        assert!(std::panic::catch_unwind(|| Q7::MAX.saturating_div(Q7::ZERO)).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "attempt to add with overflow")]
+    fn overflow_panics() {
+        let _ = Q7::MAX + Q7::from_bits(1);
+    }
+}
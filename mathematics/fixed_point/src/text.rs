@@ -0,0 +1,254 @@
+//! Fixed-point numbers as decimal text.
+//!
+//! Every fixed-point number has an exact decimal expansion, with as many
+//! digits after the point as it has fractional bits. By default the fewest
+//! digits that read back as the same number are shown, as for floats, or
+//! exactly as many as a given precision.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{Fixed, Storage};
+
+
+/// Problems reading a fixed-point number from text.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ParseFixedError {
+    /// No digits at all.
+    Empty,
+
+    /// Something other than a sign, digits, and a single decimal point.
+    InvalidDigit,
+
+    /// Number too large for the type.
+    OutOfRange,
+}
+
+
+impl fmt::Display for ParseFixedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseFixedError::Empty => {
+                write!(f, "cannot parse fixed-point number from empty string")
+            },
+            ParseFixedError::InvalidDigit => write!(f, "invalid digit found in string"),
+            ParseFixedError::OutOfRange => write!(f, "number too large for fixed-point type"),
+        }
+    }
+}
+
+impl std::error::Error for ParseFixedError {}
+
+
+/// Reads decimal text, such as `-1.25`, rounding to the nearest number with
+/// ties away from zero.
+impl<I: Storage, const FRAC: u32> FromStr for Fixed<I, FRAC> {
+    type Err = ParseFixedError;
+
+    fn from_str(text: &str) -> Result<Self, ParseFixedError> {
+        let (negative, unsigned) = match text.as_bytes().first() {
+            Some(b'-') => (true, &text[1..]),
+            Some(b'+') => (false, &text[1..]),
+            _ => (false, text),
+        };
+        let (whole, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        if whole.is_empty() && fraction.is_empty() {
+            return Err(ParseFixedError::Empty);
+        }
+        if !whole.bytes().chain(fraction.bytes()).all(|b| b.is_ascii_digit()) {
+            return Err(ParseFixedError::InvalidDigit);
+        }
+
+        // Whole part, stopping as soon as it's too large for any storage,
+        // which also leaves room for the fractional bits
+        let mut magnitude: i128 = 0;
+        for digit in whole.bytes() {
+            magnitude = magnitude * 10 + i128::from(digit - b'0');
+            if magnitude > 1 << 63 {
+                return Err(ParseFixedError::OutOfRange);
+            }
+        }
+
+        // Binary digits of the fraction, by doubling its decimal digits,
+        // and rounding on what's left over
+        let mut decimals: Vec<u8> = fraction.bytes().map(|digit| digit - b'0').collect();
+        for _ in 0..FRAC {
+            let mut carry = 0;
+            for digit in decimals.iter_mut().rev() {
+                let doubled = *digit * 2 + carry;
+                *digit = doubled % 10;
+                carry = doubled / 10;
+            }
+            magnitude = magnitude * 2 + i128::from(carry);
+        }
+        if decimals.first().is_some_and(|&digit| digit >= 5) {
+            magnitude += 1;
+        }
+
+        Self::from_i128(if negative { -magnitude } else { magnitude })
+            .ok_or(ParseFixedError::OutOfRange)
+    }
+}
+
+
+/// Shows the shortest decimal that reads back as the same number, or
+/// rounds to the given precision, to nearest with ties away from zero.
+impl<I: Storage, const FRAC: u32> fmt::Display for Fixed<I, FRAC> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bits = self.wide_bits();
+        let magnitude = bits.unsigned_abs();
+        let whole = magnitude >> FRAC;
+
+        // Exact decimal digits of the fraction
+        let mask = (1_u128 << FRAC) - 1;
+        let mut remainder = magnitude & mask;
+        let mut exact = Vec::new();
+        while remainder != 0 {
+            remainder *= 10;
+            exact.push((remainder >> FRAC) as u8);
+            remainder &= mask;
+        }
+
+        let text = match f.precision() {
+            Some(precision) => decimal(whole, &exact, precision),
+            None => (0..=exact.len())
+                .map(|places| decimal(whole, &exact, places))
+                .find(|text| {
+                    let signed = if bits < 0 { format!("-{text}") } else { text.clone() };
+                    signed.parse() == Ok(*self)
+                })
+                .expect("the exact expansion always reads back"),
+        };
+        f.pad_integral(bits >= 0, "", &text)
+    }
+}
+
+
+/// Unsigned decimal with the given number of places, rounding the exact
+/// digits of the fraction to nearest, with ties away from zero.
+fn decimal(whole: u128, exact: &[u8], places: usize) -> String {
+    let mut digits: Vec<u8> = exact.iter().copied()
+        .chain(std::iter::repeat(0))
+        .take(places)
+        .collect();
+    let mut whole = whole;
+    if exact.get(places).is_some_and(|&digit| digit >= 5) {
+        // Carry up through the digits, and into the whole part if need be
+        let mut carry = true;
+        for digit in digits.iter_mut().rev() {
+            if *digit == 9 {
+                *digit = 0;
+            } else {
+                *digit += 1;
+                carry = false;
+                break;
+            }
+        }
+        if carry {
+            whole += 1;
+        }
+    }
+
+    let mut text = whole.to_string();
+    if places > 0 {
+        text.push('.');
+        text.extend(digits.iter().map(|&digit| char::from(b'0' + digit)));
+    }
+    text
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{Q15, Q16_16, Q31, Q32_32, Q7};
+
+    #[test]
+    fn shortest() {
+        assert_eq!(Q7::from(0.5).to_string(), "0.5");
+        assert_eq!(Q7::MIN.to_string(), "-1");
+        assert_eq!(Q7::ZERO.to_string(), "0");
+        assert_eq!(Q7::MAX.to_string(), "0.99");
+        assert_eq!(Q7::from_bits(1).to_string(), "0.01");
+        assert_eq!(Q16_16::from(-3.75).to_string(), "-3.75");
+        assert_eq!(Q16_16::from(0.1).to_string(), "0.1");
+        assert_eq!(Q16_16::MAX.to_string(), "32767.99998");
+        assert_eq!(Q31::from_bits(1).to_string(), "0.0000000005");
+        assert_eq!(Q32_32::MIN.to_string(), "-2147483648");
+    }
+
+    #[test]
+    fn precision_and_padding() {
+        let x = Q16_16::from(-2.375);
+        assert_eq!(format!("{x:.2}"), "-2.38");
+        assert_eq!(format!("{x:.0}"), "-2");
+        assert_eq!(format!("{x:.5}"), "-2.37500");
+        assert_eq!(format!("{:.2}", Q16_16::from(9.999)), "10.00");
+        assert_eq!(format!("{:+}", Q7::from(0.25)), "+0.25");
+        assert_eq!(format!("{:>8}", Q7::from(-0.25)), "   -0.25");
+        assert_eq!(format!("{:08.3}", Q7::from(-0.25)), "-000.250");
+        assert_eq!(format!("{:.40}", Q7::from_bits(1)), format!("{:.40}", Q7::EPSILON));
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!("0.5".parse(), Ok(Q7::from(0.5)));
+        assert_eq!("-1".parse(), Ok(Q7::MIN));
+        assert_eq!("-1.000".parse(), Ok(Q7::MIN));
+        assert_eq!("+.25".parse(), Ok(Q7::from(0.25)));
+        assert_eq!("-.25".parse(), Ok(Q7::from(-0.25)));
+        assert_eq!("3.".parse(), Ok(Q16_16::from(3.0)));
+        assert_eq!("32767.99999".parse(), Ok(Q16_16::MAX));
+        assert_eq!("-2147483648".parse(), Ok(Q32_32::MIN));
+    }
+
+    #[test]
+    fn parse_rounds_to_nearest() {
+        // Exactly halfway between 0 and EPSILON, and a touch either side
+        assert_eq!("0.00390625".parse::<Q7>().unwrap().to_bits(), 1);
+        assert_eq!("0.00390624999999999999999".parse::<Q7>().unwrap().to_bits(), 0);
+        assert_eq!("-0.00390625".parse::<Q7>().unwrap().to_bits(), -1);
+        assert_eq!("0.99".parse::<Q7>().unwrap().to_bits(), 127);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!("".parse::<Q7>(), Err(ParseFixedError::Empty));
+        assert_eq!("-.".parse::<Q7>(), Err(ParseFixedError::Empty));
+        assert_eq!("1.2.3".parse::<Q7>(), Err(ParseFixedError::InvalidDigit));
+        assert_eq!(" 1".parse::<Q7>(), Err(ParseFixedError::InvalidDigit));
+        assert_eq!("1e3".parse::<Q7>(), Err(ParseFixedError::InvalidDigit));
+        assert_eq!("--1".parse::<Q7>(), Err(ParseFixedError::InvalidDigit));
+        assert_eq!("1".parse::<Q7>(), Err(ParseFixedError::OutOfRange));
+        assert_eq!("0.999".parse::<Q7>(), Err(ParseFixedError::OutOfRange));
+        assert_eq!("32768".parse::<Q16_16>(), Err(ParseFixedError::OutOfRange));
+        let huge = "9".repeat(100);
+        assert_eq!(huge.parse::<Q32_32>(), Err(ParseFixedError::OutOfRange));
+        let limit = format!("{}.{}", 1_u64 << 63, "9".repeat(30));
+        assert_eq!(limit.parse::<crate::Fixed<i64, 63>>(), Err(ParseFixedError::OutOfRange));
+        let whole = "9223372036854775808";
+        assert_eq!(whole.parse::<crate::Fixed<i64, 0>>(), Err(ParseFixedError::OutOfRange));
+        assert_eq!(format!("-{whole}").parse(), Ok(crate::Fixed::<i64, 0>::MIN));
+    }
+
+    #[test]
+    fn q7_round_trip_exhaustive() {
+        for bits in i8::MIN..=i8::MAX {
+            let q = Q7::from_bits(bits);
+            let text = q.to_string();
+            assert_eq!(text.parse(), Ok(q), "{text}");
+            let value: f64 = text.parse().unwrap();
+            assert!((value - q.to_f64()).abs() <= Q7::EPSILON / 2.0, "{text}");
+            assert_eq!(format!("{q:.7}").parse::<f64>().unwrap(), q.to_f64());
+        }
+    }
+
+    #[test]
+    fn q15_round_trip_exhaustive() {
+        for bits in i16::MIN..=i16::MAX {
+            let q = Q15::from_bits(bits);
+            assert_eq!(q.to_string().parse(), Ok(q));
+        }
+    }
+}
@@ -0,0 +1,443 @@
+/*!
+Morse code as audio, written out as and read back from 16-bit PCM WAV.
+
+Timing follows the "PARIS" standard: the word PARIS, with the gap after it,
+is fifty dits long, so at `w` words per minute a dit lasts `1.2 / w`
+seconds. With Farnsworth timing, characters are sent at full speed but the
+gaps between characters and words are stretched, to slow the overall, or
+effective, speed.
+*/
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::Morse::{self, *};
+
+
+/**
+Problems reading a WAV file.
+*/
+#[derive(Debug)]
+pub enum WavError {
+    Io(io::Error),
+
+    /// Not a RIFF WAVE file, missing its format or data chunks, or with a
+    /// sample rate of zero.
+    NotWav,
+
+    /// Anything but mono 16-bit PCM.
+    Unsupported { format: u16, channels: u16, bits: u16 },
+}
+
+
+impl fmt::Display for WavError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WavError::Io(error) => write!(f, "{error}"),
+            WavError::NotWav => write!(f, "not a WAV file"),
+            WavError::Unsupported { format, channels, bits } => {
+                write!(f, "only mono 16-bit PCM is supported, not format {format} \
+                    with {channels} channels of {bits} bits")
+            },
+        }
+    }
+}
+
+impl std::error::Error for WavError {}
+
+
+/**
+Sound samples, as read from a WAV file.
+*/
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Wav {
+    pub sample_rate: u32,
+    pub samples: Vec<i16>,
+}
+
+
+/**
+Settings for turning Morse elements into sound.
+*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Timing {
+    /// Pitch of the tone, in hertz.
+    pub frequency: f64,
+
+    /// Speed of each character, in words per minute.
+    pub wpm: f64,
+
+    /// Overall speed, in words per minute, no faster than `wpm`.
+    pub farnsworth_wpm: f64,
+
+    /// Samples per second.
+    pub sample_rate: u32,
+
+    /// Time taken for the tone to fade in and out, to avoid clicks.
+    pub ramp: Duration,
+
+    /// Peak volume, from zero to one.
+    pub amplitude: f64,
+}
+
+
+impl Default for Timing {
+    fn default() -> Self {
+        Timing {
+            frequency: 600.0,
+            wpm: 20.0,
+            farnsworth_wpm: 20.0,
+            sample_rate: 44_100,
+            ramp: Duration::from_millis(5),
+            amplitude: 0.8,
+        }
+    }
+}
+
+
+impl Timing {
+    /**
+    Length of a single dit, in seconds.
+    */
+    pub fn dit(&self) -> f64 {
+        1.2 / self.wpm
+    }
+
+    /**
+    Length of the given element, in seconds.
+
+    Gaps between characters and words are stretched when the Farnsworth
+    speed is below the character speed, using the ARRL's formula: the delay
+    added across a PARIS word is shared out between its four three-dit gaps
+    between characters and its seven-dit gap between words.
+    */
+    pub fn seconds(&self, element: Morse) -> f64 {
+        let dit = self.dit();
+        match element {
+            Dit | InterGap => dit,
+            Dah => 3.0 * dit,
+            ShortGap => 3.0 * self.gap_unit(),
+            MediumGap => 7.0 * self.gap_unit(),
+        }
+    }
+
+    /**
+    Total length of a sequence of elements.
+    */
+    pub fn duration(&self, elements: &[Morse]) -> Duration {
+        Duration::from_secs_f64(elements.iter().map(|&element| self.seconds(element)).sum())
+    }
+
+    /**
+    Number of samples needed for a sequence of elements.
+    */
+    pub fn sample_count(&self, elements: &[Morse]) -> usize {
+        self.sample_at(elements.iter().map(|&element| self.seconds(element)).sum())
+    }
+
+    /**
+    One unit of the gaps between characters and words, in seconds.
+    */
+    fn gap_unit(&self) -> f64 {
+        let character = self.wpm;
+        let effective = self.farnsworth_wpm.min(character);
+        let delay = (60.0 * character - 37.2 * effective) / (character * effective);
+        delay / 19.0
+    }
+
+    /**
+    Index of the sample at the given time, in seconds.
+    */
+    fn sample_at(&self, seconds: f64) -> usize {
+        (seconds * f64::from(self.sample_rate)).round() as usize
+    }
+}
+
+
+/**
+Render elements as sound samples.
+
+Each element starts on the sample nearest its exact start time, so rounding
+never builds up over long messages. The tone's phase runs on through gaps.
+*/
+pub fn synthesize(elements: &[Morse], timing: &Timing) -> Vec<i16> {
+    let rate = f64::from(timing.sample_rate);
+    let peak = timing.amplitude.clamp(0.0, 1.0) * f64::from(i16::MAX);
+    let step = 2.0 * std::f64::consts::PI * timing.frequency / rate;
+
+    let mut samples = Vec::with_capacity(timing.sample_count(elements));
+    let mut time = 0.0;
+    for &element in elements {
+        time += timing.seconds(element);
+        let start = samples.len();
+        let end = timing.sample_at(time);
+        if matches!(element, Dit | Dah) {
+            let length = end - start;
+            let ramp = timing.sample_at(timing.ramp.as_secs_f64()).min(length / 2);
+            for i in 0..length {
+                let edge = i.min(length - 1 - i);
+                let envelope = if edge < ramp {
+                    // Raised cosine, from silent to full volume
+                    0.5 - 0.5 * (std::f64::consts::PI * edge as f64 / ramp as f64).cos()
+                } else {
+                    1.0
+                };
+                let n = (start + i) as f64;
+                samples.push((peak * envelope * (step * n).sin()).round() as i16);
+            }
+        } else {
+            samples.resize(end, 0);
+        }
+    }
+    samples
+}
+
+
+/**
+Write mono 16-bit PCM samples as a WAV file.
+*/
+pub fn write_wav<W: Write>(writer: &mut W, samples: &[i16], sample_rate: u32) -> io::Result<()> {
+    const CHANNELS: u16 = 1;
+    const BITS: u16 = 16;
+    let block_align = CHANNELS * BITS / 8;
+    let data_size = u32::try_from(samples.len() * usize::from(block_align))
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "too many samples for WAV"))?;
+
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&(36 + data_size).to_le_bytes())?;
+    writer.write_all(b"WAVE")?;
+
+    writer.write_all(b"fmt ")?;
+    writer.write_all(&16_u32.to_le_bytes())?;
+    writer.write_all(&1_u16.to_le_bytes())?;                // PCM
+    writer.write_all(&CHANNELS.to_le_bytes())?;
+    writer.write_all(&sample_rate.to_le_bytes())?;
+    writer.write_all(&(sample_rate * u32::from(block_align)).to_le_bytes())?;
+    writer.write_all(&block_align.to_le_bytes())?;
+    writer.write_all(&BITS.to_le_bytes())?;
+
+    writer.write_all(b"data")?;
+    writer.write_all(&data_size.to_le_bytes())?;
+    for sample in samples {
+        writer.write_all(&sample.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+
+/**
+Synthesize elements and save them to a WAV file.
+*/
+pub fn save_wav(path: &Path, elements: &[Morse], timing: &Timing) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_wav(&mut writer, &synthesize(elements, timing), timing.sample_rate)?;
+    writer.flush()
+}
+
+
+/**
+Read mono 16-bit PCM samples from a WAV file.
+
+Chunks other than the format and the data, eg. metadata, are skipped.
+*/
+pub fn read_wav<R: Read>(reader: &mut R) -> Result<Wav, WavError> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes).map_err(WavError::Io)?;
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err(WavError::NotWav);
+    }
+    let u16_at = |chunk: &[u8], at: usize| u16::from_le_bytes([chunk[at], chunk[at + 1]]);
+    let u32_at = |chunk: &[u8], at: usize| {
+        u32::from_le_bytes([chunk[at], chunk[at + 1], chunk[at + 2], chunk[at + 3]])
+    };
+
+    let mut sample_rate = None;
+    let mut rest = &bytes[12..];
+    while rest.len() >= 8 {
+        let id = &rest[0..4];
+        let size = u32_at(rest, 4) as usize;
+        let chunk = &rest[8..rest.len().min(8 + size)];
+        match id {
+            b"fmt " => {
+                if chunk.len() < 16 {
+                    return Err(WavError::NotWav);
+                }
+                let format = u16_at(chunk, 0);
+                let channels = u16_at(chunk, 2);
+                let bits = u16_at(chunk, 14);
+                if format != 1 || channels != 1 || bits != 16 {
+                    return Err(WavError::Unsupported { format, channels, bits });
+                }
+                let rate = u32_at(chunk, 4);
+                if rate == 0 {
+                    return Err(WavError::NotWav);
+                }
+                sample_rate = Some(rate);
+            },
+            b"data" => {
+                let sample_rate = sample_rate.ok_or(WavError::NotWav)?;
+                let samples = chunk
+                    .chunks_exact(2)
+                    .map(|pair| i16::from_le_bytes([pair[0], pair[1]]))
+                    .collect();
+                return Ok(Wav { sample_rate, samples });
+            },
+            _ => {},
+        }
+        // Chunks are padded to an even length
+        rest = &rest[rest.len().min(8 + size + size % 2)..];
+    }
+    Err(WavError::NotWav)
+}
+
+
+/**
+Load mono 16-bit PCM samples from a WAV file.
+*/
+pub fn load_wav(path: &Path) -> Result<Wav, WavError> {
+    read_wav(&mut BufReader::new(File::open(path).map_err(WavError::Io)?))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encode;
+
+    /// PARIS followed by the gap between words: exactly fifty dits.
+    fn paris() -> Vec<Morse> {
+        let mut elements = encode("paris");
+        elements.push(MediumGap);
+        elements
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "expected {expected}, got {actual}");
+    }
+
+    #[test]
+    fn dit_length() {
+        assert_close(Timing::default().dit(), 0.060);
+        let slow = Timing { wpm: 5.0, farnsworth_wpm: 5.0, ..Timing::default() };
+        assert_close(slow.dit(), 0.240);
+    }
+
+    #[test]
+    fn paris_standard() {
+        let elements = paris();
+        let timing = Timing::default();
+        let dits: f64 = elements.iter().map(|&e| timing.seconds(e)).sum::<f64>() / timing.dit();
+        assert_close(dits, 50.0);
+
+        // One word at twenty words per minute
+        assert_close(timing.duration(&elements).as_secs_f64(), 3.0);
+    }
+
+    #[test]
+    fn farnsworth() {
+        let elements = paris();
+        let timing = Timing { wpm: 20.0, farnsworth_wpm: 10.0, ..Timing::default() };
+        assert_close(timing.duration(&elements).as_secs_f64(), 6.0);
+
+        // Characters themselves are still sent at full speed
+        assert_close(timing.seconds(Dah), 0.180);
+        assert_close(timing.seconds(InterGap), 0.060);
+        assert!(timing.seconds(ShortGap) > 0.180);
+
+        // Farnsworth can't speed things up
+        let fast = Timing { wpm: 20.0, farnsworth_wpm: 40.0, ..Timing::default() };
+        assert_close(fast.duration(&elements).as_secs_f64(), 3.0);
+    }
+
+    #[test]
+    fn sample_counts() {
+        let timing = Timing::default();
+        let samples = synthesize(&paris(), &timing);
+        assert_eq!(samples.len(), 3 * 44_100);
+        assert_eq!(samples.len(), timing.sample_count(&paris()));
+
+        let elements = encode("the quick brown fox");
+        let timing = Timing { wpm: 13.0, farnsworth_wpm: 7.0, sample_rate: 8_000, ..timing };
+        let expected = (timing.duration(&elements).as_secs_f64() * 8_000.0).round() as usize;
+        assert_eq!(synthesize(&elements, &timing).len(), expected);
+    }
+
+    #[test]
+    fn tone_and_silence() {
+        let timing = Timing { sample_rate: 8_000, ..Timing::default() };
+        let samples = synthesize(&[Dah, ShortGap, Dit], &timing);
+        let dah = timing.sample_at(0.180);
+        let gap = timing.sample_at(0.360);
+
+        // Ramped edges start and end near silence
+        assert!(samples[0].abs() < 100);
+        assert!(samples[dah - 1].abs() < 500);
+
+        // Full volume in the middle, and none in the gap
+        let peak = samples[..dah].iter().map(|s| s.unsigned_abs()).max().unwrap();
+        assert!(peak > (0.75 * f64::from(i16::MAX)) as u16, "peak {peak}");
+        assert!(samples[dah..gap].iter().all(|&s| s == 0));
+        assert!(samples[gap..].iter().any(|&s| s != 0));
+    }
+
+    #[test]
+    fn wav_header() {
+        let mut bytes = Vec::new();
+        write_wav(&mut bytes, &[0, 1, -1], 8_000).unwrap();
+        assert_eq!(bytes.len(), 44 + 6);
+        assert_eq!(&bytes[0..4], b"RIFF");
+        assert_eq!(u32::from_le_bytes(bytes[4..8].try_into().unwrap()), 36 + 6);
+        assert_eq!(&bytes[8..16], b"WAVEfmt ");
+        assert_eq!(u16::from_le_bytes(bytes[22..24].try_into().unwrap()), 1);
+        assert_eq!(u32::from_le_bytes(bytes[24..28].try_into().unwrap()), 8_000);
+        assert_eq!(u32::from_le_bytes(bytes[28..32].try_into().unwrap()), 16_000);
+        assert_eq!(u16::from_le_bytes(bytes[34..36].try_into().unwrap()), 16);
+        assert_eq!(&bytes[36..40], b"data");
+        assert_eq!(u32::from_le_bytes(bytes[40..44].try_into().unwrap()), 6);
+        assert_eq!(&bytes[44..], &[0, 0, 1, 0, 0xff, 0xff]);
+    }
+
+    #[test]
+    fn wav_round_trip() {
+        let timing = Timing { sample_rate: 8_000, ..Timing::default() };
+        let samples = synthesize(&encode("cq"), &timing);
+        let mut bytes = Vec::new();
+        write_wav(&mut bytes, &samples, 8_000).unwrap();
+        let wav = read_wav(&mut bytes.as_slice()).unwrap();
+        assert_eq!(wav, Wav { sample_rate: 8_000, samples });
+    }
+
+    #[test]
+    fn wav_with_extra_chunks() {
+        let mut bytes = Vec::new();
+        write_wav(&mut bytes, &[5, -5], 11_025).unwrap();
+        // Odd-sized chunk, with its padding byte, before the data
+        let list = [b"LIST".as_slice(), &3_u32.to_le_bytes(), b"abc\0"].concat();
+        bytes.splice(36..36, list);
+        assert_eq!(read_wav(&mut bytes.as_slice()).unwrap().samples, [5, -5]);
+    }
+
+    #[test]
+    fn unsupported_wav() {
+        let mut bytes = Vec::new();
+        write_wav(&mut bytes, &[0; 4], 8_000).unwrap();
+        assert!(matches!(read_wav(&mut bytes[..36].as_ref()), Err(WavError::NotWav)));
+        bytes[22] = 2;
+        assert!(matches!(
+            read_wav(&mut bytes.as_slice()),
+            Err(WavError::Unsupported { format: 1, channels: 2, bits: 16 }),
+        ));
+        assert!(matches!(read_wav(&mut b"RIFX".as_slice()), Err(WavError::NotWav)));
+    }
+
+    #[test]
+    fn zero_sample_rate() {
+        let mut bytes = Vec::new();
+        write_wav(&mut bytes, &[0; 4], 8_000).unwrap();
+        bytes[24..28].copy_from_slice(&0_u32.to_le_bytes());
+        assert!(matches!(read_wav(&mut bytes.as_slice()), Err(WavError::NotWav)));
+    }
+}
@@ -0,0 +1,490 @@
+/*!
+Code tables, from characters to strings of dots and dashes.
+
+International Morse code is always available. Other alphabets extend it,
+and are only used when asked for, as several reuse the same codes.
+*/
+
+use std::fmt;
+use std::str::FromStr;
+
+
+/**
+International Morse code, as defined by Recommendation ITU-R M.1677-1.
+
+Letters are given in lower-case only, to match `to_morse()`. The accented
+`é` is the one letter beyond ASCII in the Recommendation.
+*/
+pub const ITU: &[(char, &str)] = &[
+    // Letters
+    ('a', ".-"),
+    ('b', "-..."),
+    ('c', "-.-."),
+    ('d', "-.."),
+    ('e', "."),
+    ('f', "..-."),
+    ('g', "--."),
+    ('h', "...."),
+    ('i', ".."),
+    ('j', ".---"),
+    ('k', "-.-"),
+    ('l', ".-.."),
+    ('m', "--"),
+    ('n', "-."),
+    ('o', "---"),
+    ('p', ".--."),
+    ('q', "--.-"),
+    ('r', ".-."),
+    ('s', "..."),
+    ('t', "-"),
+    ('u', "..-"),
+    ('v', "...-"),
+    ('w', ".--"),
+    ('x', "-..-"),
+    ('y', "-.--"),
+    ('z', "--.."),
+    ('é', "..-.."),
+
+    // Figures
+    ('1', ".----"),
+    ('2', "..---"),
+    ('3', "...--"),
+    ('4', "....-"),
+    ('5', "....."),
+    ('6', "-...."),
+    ('7', "--..."),
+    ('8', "---.."),
+    ('9', "----."),
+    ('0', "-----"),
+
+    // Punctuation
+    ('.', ".-.-.-"),
+    (',', "--..--"),
+    (':', "---..."),
+    ('?', "..--.."),
+    ('\'', ".----."),
+    ('-', "-....-"),
+    ('/', "-..-."),
+    ('(', "-.--."),
+    (')', "-.--.-"),
+    ('"', ".-..-."),
+    ('=', "-...-"),
+    ('+', ".-.-."),
+    ('@', ".--.-."),
+];
+
+
+/**
+Accented Latin letters outside the ITU Recommendation, in common use for
+German, Scandinavian, Polish, Spanish, Esperanto, and other languages.
+Some share a code.
+*/
+pub const LATIN: &[(char, &str)] = &[
+    ('à', ".--.-"),
+    ('å', ".--.-"),
+    ('ä', ".-.-"),
+    ('æ', ".-.-"),
+    ('ą', ".-.-"),
+    ('ç', "-.-.."),
+    ('ć', "-.-.."),
+    ('ĉ', "-.-.."),
+    ('ð', "..--."),
+    ('è', ".-..-"),
+    ('ł', ".-..-"),
+    ('ę', "..-.."),
+    ('ĝ', "--.-."),
+    ('ĥ', "----"),
+    ('š', "----"),
+    ('ĵ', ".---."),
+    ('ñ', "--.--"),
+    ('ń', "--.--"),
+    ('ö', "---."),
+    ('ø', "---."),
+    ('ó', "---."),
+    ('ś', "...-..."),
+    ('ŝ', "...-."),
+    ('þ', ".--.."),
+    ('ü', "..--"),
+    ('ŭ', "..--"),
+    ('ź', "--..-."),
+    ('ż', "--..-"),
+];
+
+
+/**
+Russian Cyrillic, with the extra letters of Ukrainian.
+*/
+pub const CYRILLIC: &[(char, &str)] = &[
+    ('а', ".-"),
+    ('б', "-..."),
+    ('в', ".--"),
+    ('г', "--."),
+    ('д', "-.."),
+    ('е', "."),
+    ('ё', "."),
+    ('ж', "...-"),
+    ('з', "--.."),
+    ('и', ".."),
+    ('й', ".---"),
+    ('к', "-.-"),
+    ('л', ".-.."),
+    ('м', "--"),
+    ('н', "-."),
+    ('о', "---"),
+    ('п', ".--."),
+    ('р', ".-."),
+    ('с', "..."),
+    ('т', "-"),
+    ('у', "..-"),
+    ('ф', "..-."),
+    ('х', "...."),
+    ('ц', "-.-."),
+    ('ч', "---."),
+    ('ш', "----"),
+    ('щ', "--.-"),
+    ('ъ', "--.--"),
+    ('ы', "-.--"),
+    ('ь', "-..-"),
+    ('э', "..-.."),
+    ('ю', "..--"),
+    ('я', ".-.-"),
+    ('ґ', "--."),
+    ('є', "..-.."),
+    ('і', ".."),
+    ('ї', ".---."),
+];
+
+
+/**
+Greek, with final sigma sent as any other.
+*/
+pub const GREEK: &[(char, &str)] = &[
+    ('α', ".-"),
+    ('β', "-..."),
+    ('γ', "--."),
+    ('δ', "-.."),
+    ('ε', "."),
+    ('ζ', "--.."),
+    ('η', "...."),
+    ('θ', "-.-."),
+    ('ι', ".."),
+    ('κ', "-.-"),
+    ('λ', ".-.."),
+    ('μ', "--"),
+    ('ν', "-."),
+    ('ξ', "-..-"),
+    ('ο', "---"),
+    ('π', ".--."),
+    ('ρ', ".-."),
+    ('σ', "..."),
+    ('ς', "..."),
+    ('τ', "-"),
+    ('υ', "-.--"),
+    ('φ', "..-."),
+    ('χ', "----"),
+    ('ψ', "--.-"),
+    ('ω', ".--"),
+];
+
+
+/**
+Japanese Wabun code, in katakana, in iroha order.
+
+Voiced kana are sent as the plain kana followed by a dakuten, eg. ガ as カ
+then ゛, and similarly for the handakuten of パ. See `kana_parts()`.
+*/
+pub const WABUN: &[(char, &str)] = &[
+    ('イ', ".-"),
+    ('ロ', ".-.-"),
+    ('ハ', "-..."),
+    ('ニ', "-.-."),
+    ('ホ', "-.."),
+    ('ヘ', "."),
+    ('ト', "..-.."),
+    ('チ', "..-."),
+    ('リ', "--."),
+    ('ヌ', "...."),
+    ('ル', "-.--."),
+    ('ヲ', ".---"),
+    ('ワ', "-.-"),
+    ('カ', ".-.."),
+    ('ヨ', "--"),
+    ('タ', "-."),
+    ('レ', "---"),
+    ('ソ', "---."),
+    ('ツ', ".--."),
+    ('ネ', "--.-"),
+    ('ナ', ".-."),
+    ('ラ', "..."),
+    ('ム', "-"),
+    ('ウ', "..-"),
+    ('ヰ', ".-..-"),
+    ('ノ', "..--"),
+    ('オ', ".-..."),
+    ('ク', "...-"),
+    ('ヤ', ".--"),
+    ('マ', "-..-"),
+    ('ケ', "-.--"),
+    ('フ', "--.."),
+    ('コ', "----"),
+    ('エ', "-.---"),
+    ('テ', ".-.--"),
+    ('ア', "--.--"),
+    ('サ', "-.-.-"),
+    ('キ', "-.-.."),
+    ('ユ', "-..--"),
+    ('メ', "-...-"),
+    ('ミ', "..-.-"),
+    ('シ', "--.-."),
+    ('ヱ', ".--.."),
+    ('ヒ', "--..-"),
+    ('モ', "-..-."),
+    ('セ', ".---."),
+    ('ス', "---.-"),
+    ('ン', ".-.-."),
+    ('゛', ".."),
+    ('゜', "..--."),
+    ('ー', ".--.-"),
+    ('、', ".-.-.-"),
+    ('」', ".-.-.."),
+    ('（', "-.--.-"),
+    ('）', ".-..-."),
+];
+
+
+/**
+Procedural signs, sent as their letters run together without gaps, and
+written in angle brackets, eg. `<SK>`. Those sharing a code with an ITU
+character, like `<AR>` and `+`, are decoded as that character.
+*/
+pub const PROSIGNS: &[(&str, &str)] = &[
+    ("AR", ".-.-."),
+    ("AS", ".-..."),
+    ("BK", "-...-.-"),
+    ("BT", "-...-"),
+    ("CL", "-.-..-.."),
+    ("CT", "-.-.-"),
+    ("DO", "-..---"),
+    ("KN", "-.--."),
+    ("SK", "...-.-"),
+    ("SN", "...-."),
+    ("SOS", "...---..."),
+];
+
+
+/**
+Optional alphabets, used alongside International Morse code.
+*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Alphabet {
+    Latin,
+    Cyrillic,
+    Greek,
+    Wabun,
+}
+
+
+impl Alphabet {
+    pub const ALL: [Alphabet; 4] =
+        [Alphabet::Latin, Alphabet::Cyrillic, Alphabet::Greek, Alphabet::Wabun];
+
+    pub fn table(self) -> &'static [(char, &'static str)] {
+        match self {
+            Alphabet::Latin => LATIN,
+            Alphabet::Cyrillic => CYRILLIC,
+            Alphabet::Greek => GREEK,
+            Alphabet::Wabun => WABUN,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Alphabet::Latin => "latin",
+            Alphabet::Cyrillic => "cyrillic",
+            Alphabet::Greek => "greek",
+            Alphabet::Wabun => "wabun",
+        }
+    }
+}
+
+
+impl fmt::Display for Alphabet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+
+impl FromStr for Alphabet {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, String> {
+        Alphabet::ALL
+            .into_iter()
+            .find(|alphabet| alphabet.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                let names: Vec<_> = Alphabet::ALL.iter().map(|alphabet| alphabet.name()).collect();
+                format!("unknown alphabet {name:?}, expected one of: {}", names.join(", "))
+            })
+    }
+}
+
+
+/**
+Dots and dashes for the given character, if it has any.
+*/
+pub fn code(ch: char) -> Option<&'static str> {
+    find_code(ITU, ch)
+}
+
+
+/**
+Dots and dashes for the given character, trying International Morse code
+first, then each of the alphabets in turn.
+*/
+pub fn code_in(ch: char, alphabets: &[Alphabet]) -> Option<&'static str> {
+    code(ch).or_else(|| alphabets.iter().find_map(|alphabet| find_code(alphabet.table(), ch)))
+}
+
+
+/**
+Character sent as the given dots and dashes, if any.
+*/
+pub fn character(code: &str) -> Option<char> {
+    ITU.iter().find(|(_, c)| *c == code).map(|(ch, _)| *ch)
+}
+
+
+/**
+Name of the prosign sent as the given dots and dashes, if any.
+*/
+pub fn prosign(code: &str) -> Option<&'static str> {
+    PROSIGNS.iter().find(|(_, c)| *c == code).map(|(name, _)| *name)
+}
+
+
+/**
+Kana as sent in Wabun: hiragana as katakana, small kana as full-sized, and
+voiced kana as the plain kana followed by a dakuten or handakuten.
+*/
+pub fn kana_parts(ch: char) -> (char, Option<char>) {
+    // Hiragana sit a fixed distance below their katakana
+    let ch = match ch {
+        'ぁ'..='ゖ' => char::from_u32(u32::from(ch) + 0x60).unwrap_or(ch),
+        _ => ch,
+    };
+    // Small and voiced kana sit next to their plain kana
+    let shift = |ch: char, by: i32| {
+        char::from_u32(u32::from(ch).wrapping_add_signed(by)).unwrap_or(ch)
+    };
+    match ch {
+        'ァ' | 'ィ' | 'ゥ' | 'ェ' | 'ォ' | 'ッ' | 'ャ' | 'ュ' | 'ョ' | 'ヮ' => (shift(ch, 1), None),
+        'ヴ' => ('ウ', Some('゛')),
+        'ガ' | 'ギ' | 'グ' | 'ゲ' | 'ゴ' | 'ザ' | 'ジ' | 'ズ' | 'ゼ' | 'ゾ'
+        | 'ダ' | 'ヂ' | 'ヅ' | 'デ' | 'ド' | 'バ' | 'ビ' | 'ブ' | 'ベ' | 'ボ' => (shift(ch, -1), Some('゛')),
+        'パ' | 'ピ' | 'プ' | 'ペ' | 'ポ' => (shift(ch, -2), Some('゜')),
+        _ => (ch, None),
+    }
+}
+
+
+/**
+Code for a character in one table.
+*/
+fn find_code(table: &[(char, &'static str)], ch: char) -> Option<&'static str> {
+    table.iter().find(|(c, _)| *c == ch).map(|(_, code)| *code)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::is_morseable;
+
+    #[test]
+    fn every_morseable_byte_has_a_code() {
+        for byte in 0..=127_u8 {
+            if is_morseable(byte) && byte != b' ' {
+                assert!(code(char::from(byte)).is_some(), "no code for {:?}", char::from(byte));
+            }
+        }
+    }
+
+    #[test]
+    fn codes_are_unique() {
+        for (i, (a, code_a)) in ITU.iter().enumerate() {
+            for (b, code_b) in &ITU[i + 1..] {
+                assert_ne!(code_a, code_b, "{a:?} and {b:?} share a code");
+            }
+        }
+    }
+
+    #[test]
+    fn lookup_both_ways() {
+        assert_eq!(code('k'), Some("-.-"));
+        assert_eq!(character("-.-"), Some('k'));
+        assert_eq!(code('K'), None);
+        assert_eq!(character("........"), None);
+    }
+
+    #[test]
+    fn only_dots_and_dashes() {
+        let tables = Alphabet::ALL.iter().map(|alphabet| alphabet.table()).chain([ITU]);
+        for table in tables {
+            assert!(table.iter().all(|(_, code)| code.chars().all(|c| c == '.' || c == '-')));
+        }
+        assert!(PROSIGNS.iter().all(|(_, code)| code.chars().all(|c| c == '.' || c == '-')));
+    }
+
+    #[test]
+    fn alphabet_codes_are_unique() {
+        // Other than the letters deliberately shared
+        for table in [CYRILLIC, GREEK, WABUN] {
+            for (i, (a, code_a)) in table.iter().enumerate() {
+                for (b, code_b) in &table[i + 1..] {
+                    let shared = matches!(
+                        (a, b),
+                        ('е', 'ё') | ('σ', 'ς') | ('э', 'є') | ('г', 'ґ') | ('и', 'і'),
+                    );
+                    assert!(shared || code_a != code_b, "{a:?} and {b:?} share a code");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn prosigns_are_letters_run_together() {
+        for (name, code) in PROSIGNS {
+            let letters: String = name
+                .chars()
+                .map(|ch| super::code(ch.to_ascii_lowercase()).unwrap())
+                .collect();
+            assert_eq!(&letters, code, "<{name}>");
+        }
+        assert_eq!(prosign("...-.-"), Some("SK"));
+        assert_eq!(prosign(".-"), None);
+    }
+
+    #[test]
+    fn alphabets_in_order() {
+        assert_eq!(code_in('ж', &[]), None);
+        assert_eq!(code_in('ж', &[Alphabet::Cyrillic]), Some("...-"));
+        assert_eq!(code_in('é', &[]), Some("..-.."));
+        assert_eq!(code_in('ш', &[Alphabet::Greek, Alphabet::Cyrillic]), Some("----"));
+        assert_eq!(code_in('a', &Alphabet::ALL), Some(".-"));
+        assert_eq!("GREEK".parse(), Ok(Alphabet::Greek));
+        assert!("klingon".parse::<Alphabet>().unwrap_err().contains("latin, cyrillic, greek, wabun"));
+    }
+
+    #[test]
+    fn kana() {
+        assert_eq!(kana_parts('カ'), ('カ', None));
+        assert_eq!(kana_parts('か'), ('カ', None));
+        assert_eq!(kana_parts('ガ'), ('カ', Some('゛')));
+        assert_eq!(kana_parts('ぽ'), ('ホ', Some('゜')));
+        assert_eq!(kana_parts('ッ'), ('ツ', None));
+        assert_eq!(kana_parts('ょ'), ('ヨ', None));
+        assert_eq!(kana_parts('ヴ'), ('ウ', Some('゛')));
+        assert_eq!(kana_parts('a'), ('a', None));
+    }
+}
@@ -0,0 +1,418 @@
+/*!
+Morse code back to text, from dots and dashes or from timed key events.
+*/
+
+use std::fmt;
+use std::time::Duration;
+
+use crate::codes;
+use crate::Morse::{self, *};
+
+
+/**
+Problems found while decoding.
+*/
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// Sequence of dots and dashes with no character, at the given
+    /// character index of the decoded text.
+    UnknownCode { code: String, position: usize },
+
+    /// Something other than a dot, dash, space, or slash, at the given
+    /// byte offset of the input.
+    InvalidSymbol { symbol: char, position: usize },
+}
+
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::UnknownCode { code, position } => {
+                write!(f, "unknown code {code:?} for character {position}")
+            },
+            DecodeError::InvalidSymbol { symbol, position } => {
+                write!(f, "invalid symbol {symbol:?} at position {position}")
+            },
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+
+/**
+A key held down or released, for the given length of time.
+*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub on: bool,
+    pub duration: Duration,
+}
+
+
+/**
+Decode dots and dashes, as produced by `render()`.
+
+Characters are separated by whitespace, and words by a `/`.
+*/
+pub fn decode_text(text: &str) -> Result<String, DecodeError> {
+    if let Some((position, symbol)) = text
+        .char_indices()
+        .find(|&(_, c)| !matches!(c, '.' | '-' | '/') && !c.is_whitespace())
+    {
+        return Err(DecodeError::InvalidSymbol { symbol, position });
+    }
+
+    let mut decoded = String::new();
+    for (i, word) in text.split('/').map(str::trim).filter(|word| !word.is_empty()).enumerate() {
+        if i > 0 {
+            decoded.push(' ');
+        }
+        for code in word.split_whitespace() {
+            decoded.push_str(&lookup(code, decoded.chars().count())?);
+        }
+    }
+    Ok(decoded)
+}
+
+
+/**
+Decode a sequence of Morse elements, as produced by `encode()`.
+*/
+pub fn decode_elements(elements: &[Morse]) -> Result<String, DecodeError> {
+    let mut decoded = String::new();
+    let mut code = String::new();
+    for element in elements {
+        match element {
+            Dit => code.push('.'),
+            Dah => code.push('-'),
+            InterGap => {},
+            ShortGap | MediumGap => {
+                if !code.is_empty() {
+                    decoded.push_str(&lookup(&code, decoded.chars().count())?);
+                    code.clear();
+                }
+                if *element == MediumGap && !decoded.is_empty() && !decoded.ends_with(' ') {
+                    decoded.push(' ');
+                }
+            },
+        }
+    }
+    if !code.is_empty() {
+        decoded.push_str(&lookup(&code, decoded.chars().count())?);
+    }
+    Ok(decoded.trim_end().to_string())
+}
+
+
+/**
+Decode timed key events, adapting to the sender's speed.
+*/
+pub fn decode_timed(events: &[KeyEvent]) -> Result<String, DecodeError> {
+    decode_elements(&classify(events))
+}
+
+
+/**
+Turn timed key events into Morse elements, adapting to the sender's speed.
+
+The length of a dit is first estimated by splitting the lengths of key
+presses into two clusters, short and long. If they don't separate, eg.
+when every element sent is a dit, all presses are taken as dits unless
+they're at least twice the shortest gap. As the events are classified the
+estimate tracks the sender, so gradual changes in speed are followed.
+
+Presses under two dits long are dits, otherwise dahs, and gaps under two
+dits are between elements. Longer gaps are between characters or words:
+they're split into two clusters like the presses, so that Farnsworth
+timing, with its stretched gaps, is followed. Without a clear split, gaps
+under five dits are between characters. Gaps at either end are ignored.
+
+Without any dahs, some messages are ambiguous: `ttt` sent alone can't be
+told apart from `s`, and is decoded as the latter.
+*/
+pub fn classify(events: &[KeyEvent]) -> Vec<Morse> {
+    let seconds = |event: &KeyEvent| event.duration.as_secs_f64();
+    let first = events.iter().position(|event| event.on);
+    let last = events.iter().rposition(|event| event.on);
+    let (Some(first), Some(last)) = (first, last) else {
+        return Vec::new();
+    };
+    let events = &events[first..=last];
+
+    let presses: Vec<f64> = events.iter().filter(|event| event.on).map(seconds).collect();
+    let shortest_gap = events
+        .iter()
+        .filter(|event| !event.on)
+        .map(seconds)
+        .fold(f64::INFINITY, f64::min);
+    let mut unit = estimate_unit(&presses, shortest_gap);
+
+    // Merge runs of events in the same state, eg. from key bounce, and
+    // measure each in dits as the estimate follows the sender
+    let mut runs = Vec::new();
+    let mut i = 0;
+    while i < events.len() {
+        let on = events[i].on;
+        let mut length = 0.0;
+        while i < events.len() && events[i].on == on {
+            length += seconds(&events[i]);
+            i += 1;
+        }
+        let units = length / unit;
+        if on {
+            let expected = if units < 2.0 { 1.0 } else { 3.0 };
+            unit = 0.8 * unit + 0.2 * length / expected;
+        }
+        runs.push((on, units));
+    }
+
+    let long_gaps: Vec<f64> = runs
+        .iter()
+        .filter(|&&(on, units)| !on && units >= 2.0)
+        .map(|&(_, units)| units)
+        .collect();
+    let word_gap = word_gap_threshold(&long_gaps);
+
+    runs.iter()
+        .map(|&(on, units)| match (on, units) {
+            (true, units) if units < 2.0 => Dit,
+            (true, _) => Dah,
+            (false, units) if units < 2.0 => InterGap,
+            (false, units) if units < word_gap => ShortGap,
+            (false, _) => MediumGap,
+        })
+        .collect()
+}
+
+
+/**
+Shortest gap between words, in dits, given every gap of two dits or more.
+*/
+fn word_gap_threshold(gaps: &[f64]) -> f64 {
+    match best_split(gaps) {
+        Some((short, long)) if long >= 1.6 * short => (short + long) / 2.0,
+        _ => 5.0,
+    }
+}
+
+
+/**
+First guess at the length of a dit, in seconds.
+*/
+fn estimate_unit(presses: &[f64], shortest_gap: f64) -> f64 {
+    if let Some((short, long)) = best_split(presses) {
+        if long >= 2.0 * short {
+            return (short + long / 3.0) / 2.0;
+        }
+    }
+
+    // Only one length of press
+    let press = presses.iter().sum::<f64>() / presses.len() as f64;
+    if press >= 2.0 * shortest_gap {
+        press / 3.0
+    } else {
+        press
+    }
+}
+
+
+/**
+Means of the short and long values, split in two where that gives the
+least squared error. Returns `None` with fewer than two values.
+*/
+fn best_split(values: &[f64]) -> Option<(f64, f64)> {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let mean = |values: &[f64]| values.iter().sum::<f64>() / values.len() as f64;
+
+    let mut best: Option<(f64, usize)> = None;
+    for split in 1..sorted.len() {
+        let (short, long) = sorted.split_at(split);
+        let (short_mean, long_mean) = (mean(short), mean(long));
+        let error: f64 = short.iter().map(|x| (x - short_mean).powi(2)).sum::<f64>()
+            + long.iter().map(|x| (x - long_mean).powi(2)).sum::<f64>();
+        if best.is_none_or(|(best_error, _)| error < best_error) {
+            best = Some((error, split));
+        }
+    }
+    let (_, split) = best?;
+    let (short, long) = sorted.split_at(split);
+    Some((mean(short), mean(long)))
+}
+
+
+/**
+Character or bracketed prosign for a code, or an error naming its position
+in the text.
+*/
+fn lookup(code: &str, position: usize) -> Result<String, DecodeError> {
+    codes::character(code)
+        .map(String::from)
+        .or_else(|| codes::prosign(code).map(|name| format!("<{name}>")))
+        .ok_or_else(|| DecodeError::UnknownCode { code: code.to_string(), position })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{encode, render};
+
+    const PANGRAM: &str = "sphinx of black quartz, judge my vow: 0123456789 (\"'+-./=?@)";
+
+    /**
+    Key events for text sent at the given speed, with each event's length
+    changed by up to `jitter`, as a fraction.
+    */
+    fn key_events(text: &str, unit: impl Fn(usize) -> f64, jitter: f64) -> Vec<KeyEvent> {
+        encode(text)
+            .iter()
+            .enumerate()
+            .map(|(i, element)| {
+                let (on, units) = match element {
+                    Dit => (true, 1.0),
+                    Dah => (true, 3.0),
+                    InterGap => (false, 1.0),
+                    ShortGap => (false, 3.0),
+                    MediumGap => (false, 7.0),
+                };
+                // Repeatable noise in -1..1
+                let noise = ((i as f64 * 12.9898).sin() * 43758.5453).fract();
+                let seconds = units * unit(i) * (1.0 + jitter * noise);
+                KeyEvent { on, duration: Duration::from_secs_f64(seconds) }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn decode_text_round_trip() {
+        let text = render(&encode(PANGRAM));
+        assert_eq!(decode_text(&text).unwrap(), PANGRAM);
+    }
+
+    #[test]
+    fn decode_text_spacing() {
+        assert_eq!(decode_text("  ... ---   .../.. ").unwrap(), "sos i");
+        assert_eq!(decode_text("").unwrap(), "");
+    }
+
+    #[test]
+    fn decode_text_unknown_code() {
+        assert_eq!(
+            decode_text("... -------- ...").unwrap_err(),
+            DecodeError::UnknownCode { code: "--------".to_string(), position: 1 },
+        );
+        assert_eq!(
+            decode_text(".- / ........").unwrap_err().to_string(),
+            "unknown code \"........\" for character 2",
+        );
+    }
+
+    #[test]
+    fn decode_text_invalid_symbol() {
+        assert_eq!(
+            decode_text(".- _").unwrap_err(),
+            DecodeError::InvalidSymbol { symbol: '_', position: 3 },
+        );
+    }
+
+    #[test]
+    fn decode_prosigns() {
+        assert_eq!(decode_text("-.-. --.- / ...-.-").unwrap(), "cq <SK>");
+        // Shared with ITU characters
+        assert_eq!(decode_text(".-.-. -...-").unwrap(), "+=");
+        let text = "qrt <SK> <CL>";
+        assert_eq!(decode_elements(&encode(text)).unwrap(), text);
+    }
+
+    #[test]
+    fn decode_elements_round_trip() {
+        assert_eq!(decode_elements(&encode(PANGRAM)).unwrap(), PANGRAM);
+        assert_eq!(decode_elements(&[]).unwrap(), "");
+    }
+
+    #[test]
+    fn decode_elements_unknown_code() {
+        let elements = [Dit, InterGap, Dit, ShortGap, Dah, Dah, Dah, Dah, Dah, Dah];
+        assert_eq!(
+            decode_elements(&elements).unwrap_err(),
+            DecodeError::UnknownCode { code: "------".to_string(), position: 1 },
+        );
+    }
+
+    #[test]
+    fn timed_steady() {
+        // 20 WPM is a 60ms dit
+        let events = key_events(PANGRAM, |_| 0.060, 0.0);
+        assert_eq!(decode_timed(&events).unwrap(), PANGRAM);
+    }
+
+    #[test]
+    fn timed_jittery() {
+        let events = key_events(PANGRAM, |_| 0.100, 0.25);
+        assert_eq!(decode_timed(&events).unwrap(), PANGRAM);
+    }
+
+    #[test]
+    fn timed_speeding_up() {
+        // From 8 WPM to 30 WPM over the message
+        let count = encode(PANGRAM).len() as f64;
+        let events = key_events(PANGRAM, |i| 0.150 - 0.110 * i as f64 / count, 0.1);
+        assert_eq!(decode_timed(&events).unwrap(), PANGRAM);
+    }
+
+    #[test]
+    fn timed_farnsworth() {
+        // Gaps between characters and words stretched four times over
+        let mut events = key_events(PANGRAM, |_| 0.060, 0.1);
+        for event in events.iter_mut().filter(|event| !event.on) {
+            if event.duration > Duration::from_millis(120) {
+                event.duration *= 4;
+            }
+        }
+        assert_eq!(decode_timed(&events).unwrap(), PANGRAM);
+    }
+
+    #[test]
+    fn timed_dits_only() {
+        let events = key_events("eee ish", |_| 0.080, 0.0);
+        assert_eq!(decode_timed(&events).unwrap(), "eee ish");
+    }
+
+    #[test]
+    fn timed_dahs_only() {
+        let events = key_events("mo to", |_| 0.080, 0.0);
+        assert_eq!(decode_timed(&events).unwrap(), "mo to");
+    }
+
+    #[test]
+    fn timed_edges_and_bounce() {
+        let ms = |on, ms| KeyEvent { on, duration: Duration::from_millis(ms) };
+        let events = [
+            ms(false, 2_000),
+            ms(true, 60),
+            ms(false, 60),
+            ms(true, 100), ms(true, 80),
+            ms(false, 500),
+        ];
+        assert_eq!(classify(&events), vec![Dit, InterGap, Dah]);
+        assert_eq!(decode_timed(&events).unwrap(), "a");
+        assert_eq!(decode_timed(&[ms(false, 100)]).unwrap(), "");
+    }
+
+    #[test]
+    fn timed_unknown_code() {
+        let mut events = key_events("ee", |_| 0.060, 0.0);
+        let gap = KeyEvent { on: false, duration: Duration::from_millis(60) };
+        let dit = KeyEvent { on: true, duration: Duration::from_millis(60) };
+        for _ in 0..8 {
+            events.push(gap);
+            events.push(dit);
+        }
+        assert!(matches!(
+            decode_timed(&events),
+            Err(DecodeError::UnknownCode { position: 1, .. }),
+        ));
+    }
+}
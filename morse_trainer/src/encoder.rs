@@ -0,0 +1,209 @@
+/*!
+Text to Morse code elements, and back out as dots and dashes.
+*/
+
+use crate::codes::{self, Alphabet};
+use crate::Morse::{self, *};
+
+
+/**
+Encode text as a sequence of Morse elements, using International Morse
+code only.
+
+See `encode_with()`.
+*/
+pub fn encode(text: &str) -> Vec<Morse> {
+    encode_with(text, &[])
+}
+
+
+/**
+Encode text as a sequence of Morse elements, with optional alphabets.
+
+Characters are looked up in International Morse code first, then in each
+alphabet in turn, and dropped if none has them. Letters in angle brackets
+are sent run together as a prosign, eg. `<SK>` for `...-.-`.
+
+Elements within a character are separated by an `InterGap`, characters by
+a `ShortGap`, and words by a `MediumGap`. There is no gap at either end.
+*/
+pub fn encode_with(text: &str, alphabets: &[Alphabet]) -> Vec<Morse> {
+    let mut elements = Vec::new();
+    for word in text.split_whitespace() {
+        let codes = word_codes(word, alphabets);
+        if codes.is_empty() {
+            continue;
+        }
+        if !elements.is_empty() {
+            elements.push(MediumGap);
+        }
+        for (i, code) in codes.iter().enumerate() {
+            if i > 0 {
+                elements.push(ShortGap);
+            }
+            push_code(&mut elements, code);
+        }
+    }
+    elements
+}
+
+
+/**
+Dots and dashes for each character and prosign in a word.
+*/
+fn word_codes(word: &str, alphabets: &[Alphabet]) -> Vec<String> {
+    let mut codes = Vec::new();
+    let mut rest = word;
+    while let Some(ch) = rest.chars().next() {
+        if ch == '<' {
+            if let Some(end) = rest.find('>') {
+                // Every letter must have a code
+                let letters: Option<Vec<Vec<&str>>> = rest[1..end]
+                    .chars()
+                    .map(|ch| {
+                        let codes = character_codes(ch, alphabets);
+                        (!codes.is_empty()).then_some(codes)
+                    })
+                    .collect();
+                if let Some(letters) = letters.filter(|letters| !letters.is_empty()) {
+                    codes.push(letters.concat().concat());
+                    rest = &rest[end + 1..];
+                    continue;
+                }
+            }
+        }
+        codes.extend(character_codes(ch, alphabets).into_iter().map(String::from));
+        rest = &rest[ch.len_utf8()..];
+    }
+    codes
+}
+
+
+/**
+Codes sending a single character, which may take more than one for kana.
+*/
+fn character_codes(ch: char, alphabets: &[Alphabet]) -> Vec<&'static str> {
+    let mut codes = Vec::new();
+    for ch in ch.to_lowercase() {
+        if let Some(code) = codes::code_in(ch, alphabets) {
+            codes.push(code);
+        } else if alphabets.contains(&Alphabet::Wabun) {
+            let (kana, mark) = codes::kana_parts(ch);
+            let parts = [Some(kana), mark].into_iter().flatten();
+            codes.extend(parts.filter_map(|ch| codes::code_in(ch, alphabets)));
+        }
+    }
+    codes
+}
+
+
+/**
+Append a single character's elements, from its dots and dashes.
+*/
+pub(crate) fn push_code(elements: &mut Vec<Morse>, code: &str) {
+    for (i, symbol) in code.chars().enumerate() {
+        if i > 0 {
+            elements.push(InterGap);
+        }
+        elements.push(if symbol == '.' { Dit } else { Dah });
+    }
+}
+
+
+/**
+Render elements as dots and dashes.
+
+Characters are separated by a single space, and words by ` / `, eg.
+`... --- ...` or `.- / -...`.
+*/
+pub fn render(elements: &[Morse]) -> String {
+    elements
+        .iter()
+        .map(|element| match element {
+            Dit => ".",
+            Dah => "-",
+            InterGap => "",
+            ShortGap => " ",
+            MediumGap => " / ",
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_letter() {
+        assert_eq!(encode("a"), vec![Dit, InterGap, Dah]);
+        assert_eq!(encode("e"), vec![Dit]);
+    }
+
+    #[test]
+    fn encode_gaps() {
+        assert_eq!(
+            encode("et te"),
+            vec![Dit, ShortGap, Dah, MediumGap, Dah, ShortGap, Dit],
+        );
+    }
+
+    #[test]
+    fn encode_filters_text() {
+        assert_eq!(encode("  E!  🦀 T "), encode("e t"));
+        assert_eq!(encode(""), vec![]);
+    }
+
+    #[test]
+    fn encode_prosigns() {
+        assert_eq!(render(&encode("<AR>")), ".-.-.");
+        assert_eq!(
+            render(&encode("cq de m0abc <bt> qrl? <sk>")),
+            render(&encode("cq de m0abc = qrl?")) + " / ...-.-",
+        );
+        assert_eq!(render(&encode("73<SK>")), "--... ...-- ...-.-");
+        // Not prosigns, so just their letters
+        assert_eq!(render(&encode("<> a<b c>")), ".- -... / -.-.");
+        assert_eq!(render(&encode("<a🦀>")), ".-");
+        assert_eq!(render(&encode_with("<жж>", &[Alphabet::Cyrillic])), "...-...-");
+    }
+
+    #[test]
+    fn encode_alphabets() {
+        // Without the alphabet, all but ITU is dropped
+        assert_eq!(render(&encode("Été ÅÄÖ")), "..-.. - ..-..");
+        assert_eq!(
+            render(&encode_with("Été ÅÄÖ", &[Alphabet::Latin])),
+            "..-.. - ..-.. / .--.- .-.- ---.",
+        );
+        assert_eq!(render(&encode_with("Привет", &[Alphabet::Cyrillic])), ".--. .-. .. .-- . -");
+        assert_eq!(render(&encode_with("Ωμέγα", &[Alphabet::Greek])), ".-- -- --. .-");
+        assert_eq!(render(&encode_with("СОС sos", &[Alphabet::Cyrillic])), "... --- ... / ... --- ...");
+    }
+
+    #[test]
+    fn encode_wabun() {
+        assert_eq!(render(&encode_with("イロハ", &[Alphabet::Wabun])), ".- .-.- -...");
+        // Hiragana, voiced, and small kana
+        assert_eq!(render(&encode_with("がっこう", &[Alphabet::Wabun])), ".-.. .. .--. ---- ..-");
+        assert_eq!(render(&encode_with("ポ", &[Alphabet::Wabun])), "-.. ..--.");
+        assert_eq!(encode("イロハ"), vec![]);
+    }
+
+    #[test]
+    fn render_sos() {
+        assert_eq!(render(&encode("SOS")), "... --- ...");
+    }
+
+    #[test]
+    fn render_words() {
+        assert_eq!(
+            render(&encode("Hello, world")),
+            ".... . .-.. .-.. --- --..-- / .-- --- .-. .-.. -..",
+        );
+        assert_eq!(
+            render(&encode("73 @ 10:30?")),
+            "--... ...-- / .--.-. / .---- ----- ---... ...-- ----- ..--..",
+        );
+    }
+}
@@ -0,0 +1,402 @@
+/*!
+Learning Morse by the Koch method.
+
+Characters are always sent at full speed, starting with just two of them.
+Once random groups of the characters learnt so far are copied with 90%
+accuracy, a new character is added, until the whole alphabet is known.
+*/
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+
+/**
+Order in which characters are learnt, as used by LCWO and most other Koch
+method courses.
+*/
+pub const KOCH_ORDER: &str = "kmuresnaptlwi.jz=foy,vg5/q92h38b?47c1d60x";
+
+/// Characters known before the first lesson.
+pub const FIRST_LESSON: usize = 2;
+
+/// Accuracy needed, from zero to one, to move on to the next character.
+pub const PASS_MARK: f64 = 0.9;
+
+
+/**
+Count of characters sent, and of how many were copied correctly.
+*/
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tally {
+    pub sent: u32,
+    pub correct: u32,
+}
+
+
+impl Tally {
+    /**
+    Fraction copied correctly, or `None` if nothing has been sent.
+    */
+    pub fn accuracy(&self) -> Option<f64> {
+        (self.sent > 0).then(|| f64::from(self.correct) / f64::from(self.sent))
+    }
+
+    fn add(&mut self, other: Tally) {
+        self.sent += other.sent;
+        self.correct += other.correct;
+    }
+}
+
+
+/**
+How well a single session was copied, character by character.
+*/
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Score {
+    pub characters: BTreeMap<char, Tally>,
+
+    /// Characters copied that were never sent.
+    pub extra: u32,
+}
+
+
+impl Score {
+    /**
+    Compare what was sent with the student's copy.
+
+    The copy is lined up with what was sent by the fewest edits, so a
+    missed or extra character only counts against itself, not everything
+    after it. Extra characters belong to no character sent, so are counted
+    apart, in `extra`. Case and the amount of whitespace don't matter, and
+    spaces aren't scored.
+    */
+    pub fn new(sent: &str, copied: &str) -> Self {
+        let normalise = |text: &str| -> Vec<char> {
+            text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase().chars().collect()
+        };
+        let (sent, copied) = (normalise(sent), normalise(copied));
+
+        // Edit distances between every pair of prefixes
+        let mut distances = vec![vec![0_usize; copied.len() + 1]; sent.len() + 1];
+        for (i, row) in distances.iter_mut().enumerate() {
+            row[0] = i;
+        }
+        for (j, distance) in distances[0].iter_mut().enumerate() {
+            *distance = j;
+        }
+        for i in 1..=sent.len() {
+            for j in 1..=copied.len() {
+                let substitution = usize::from(sent[i - 1] != copied[j - 1]);
+                distances[i][j] = (distances[i - 1][j - 1] + substitution)
+                    .min(distances[i - 1][j] + 1)
+                    .min(distances[i][j - 1] + 1);
+            }
+        }
+
+        // Walk back along a cheapest path, marking matched characters
+        let mut score = Score::default();
+        let (mut i, mut j) = (sent.len(), copied.len());
+        while i > 0 {
+            let matched = j > 0 && sent[i - 1] == copied[j - 1]
+                && distances[i][j] == distances[i - 1][j - 1];
+            if matched {
+                score.count(sent[i - 1], true);
+                i -= 1;
+                j -= 1;
+            } else if j > 0 && distances[i][j] == distances[i - 1][j - 1] + 1 {
+                score.count(sent[i - 1], false);
+                i -= 1;
+                j -= 1;
+            } else if distances[i][j] == distances[i - 1][j] + 1 {
+                score.count(sent[i - 1], false);
+                i -= 1;
+            } else {
+                score.extra += u32::from(copied[j - 1] != ' ');
+                j -= 1;
+            }
+        }
+
+        // Anything copied before the first character sent
+        score.extra += copied[..j].iter().filter(|&&ch| ch != ' ').count() as u32;
+        score
+    }
+
+    /**
+    Characters sent and copied across the whole session.
+    */
+    pub fn total(&self) -> Tally {
+        let mut total = Tally::default();
+        for &tally in self.characters.values() {
+            total.add(tally);
+        }
+        total
+    }
+
+    /**
+    Fraction of the session copied correctly, with every extra character
+    counting as a mistake, so that typing more can't help. Zero if nothing
+    was sent or copied.
+    */
+    pub fn accuracy(&self) -> f64 {
+        let total = self.total();
+        let attempts = total.sent + self.extra;
+        if attempts == 0 {
+            return 0.0;
+        }
+        f64::from(total.correct) / f64::from(attempts)
+    }
+
+    fn count(&mut self, ch: char, correct: bool) {
+        if ch != ' ' {
+            let tally = self.characters.entry(ch).or_default();
+            tally.sent += 1;
+            tally.correct += u32::from(correct);
+        }
+    }
+}
+
+
+/**
+Problems loading or saving progress.
+*/
+#[derive(Debug)]
+pub enum ProgressError {
+    Io(io::Error),
+    Json(serde_json::Error),
+
+    /// Saved lesson is outside the course.
+    BadLesson(usize),
+}
+
+
+impl fmt::Display for ProgressError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProgressError::Io(error) => write!(f, "{error}"),
+            ProgressError::Json(error) => write!(f, "{error}"),
+            ProgressError::BadLesson(lesson) => {
+                write!(f, "lesson {lesson} is outside the course of {FIRST_LESSON} to {}",
+                    KOCH_ORDER.len())
+            },
+        }
+    }
+}
+
+impl std::error::Error for ProgressError {}
+
+
+/**
+A student's progress through the course, saved between sessions.
+*/
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Progress {
+    /// Number of characters learnt so far.
+    pub lesson: usize,
+
+    /// Number of sessions completed.
+    pub sessions: u32,
+
+    /// Copying accuracy of each character, over every session.
+    pub characters: BTreeMap<char, Tally>,
+}
+
+
+impl Default for Progress {
+    fn default() -> Self {
+        Progress { lesson: FIRST_LESSON, sessions: 0, characters: BTreeMap::new() }
+    }
+}
+
+
+impl Progress {
+    /**
+    Load progress from a JSON file, starting afresh if there isn't one.
+    */
+    pub fn load(path: &Path) -> Result<Self, ProgressError> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Progress::default()),
+            Err(error) => return Err(ProgressError::Io(error)),
+        };
+        let progress: Progress = serde_json::from_str(&text).map_err(ProgressError::Json)?;
+        if !(FIRST_LESSON..=KOCH_ORDER.len()).contains(&progress.lesson) {
+            return Err(ProgressError::BadLesson(progress.lesson));
+        }
+        Ok(progress)
+    }
+
+    /**
+    Save progress to a JSON file.
+    */
+    pub fn save(&self, path: &Path) -> Result<(), ProgressError> {
+        let text = serde_json::to_string_pretty(self).map_err(ProgressError::Json)?;
+        fs::write(path, text + "\n").map_err(ProgressError::Io)
+    }
+
+    /**
+    Characters learnt so far, in the order they were introduced.
+    */
+    pub fn learnt(&self) -> Vec<char> {
+        KOCH_ORDER.chars().take(self.lesson).collect()
+    }
+
+    /**
+    True once every character has been learnt.
+    */
+    pub fn finished(&self) -> bool {
+        self.lesson >= KOCH_ORDER.len()
+    }
+
+    /**
+    Add a session's score, returning the newly unlocked character if the
+    pass mark was reached.
+    */
+    pub fn record(&mut self, score: &Score) -> Option<char> {
+        self.sessions += 1;
+        for (&ch, &tally) in &score.characters {
+            self.characters.entry(ch).or_default().add(tally);
+        }
+        if score.accuracy() >= PASS_MARK && !self.finished() {
+            self.lesson += 1;
+            KOCH_ORDER.chars().nth(self.lesson - 1)
+        } else {
+            None
+        }
+    }
+}
+
+
+/**
+Random groups of the given characters, separated by spaces.
+*/
+pub fn random_groups<R: Rng>(
+    rng: &mut R,
+    characters: &[char],
+    groups: usize,
+    group_size: usize,
+) -> String {
+    assert!(!characters.is_empty(), "groups need at least one character");
+    (0..groups)
+        .map(|_| (0..group_size).map(|_| characters[rng.gen_range(0..characters.len())]).collect())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+
+    use crate::codes;
+
+    fn tally(sent: u32, correct: u32) -> Tally {
+        Tally { sent, correct }
+    }
+
+    #[test]
+    fn course_is_morseable() {
+        let mut seen: Vec<char> = KOCH_ORDER.chars().collect();
+        assert!(KOCH_ORDER.chars().all(|ch| codes::code(ch).is_some()));
+        seen.sort_unstable();
+        seen.dedup();
+        assert_eq!(seen.len(), KOCH_ORDER.len());
+    }
+
+    #[test]
+    fn perfect_copy() {
+        let score = Score::new("kmmkk mkkmm", "KMMKK   mkkmm\n");
+        assert_eq!(score.characters[&'k'], tally(5, 5));
+        assert_eq!(score.characters[&'m'], tally(5, 5));
+        assert_eq!(score.accuracy(), 1.0);
+    }
+
+    #[test]
+    fn missed_character_only_counts_once() {
+        let score = Score::new("kmuk ruse", "kuk ruse");
+        assert_eq!(score.characters[&'m'], tally(1, 0));
+        assert_eq!(score.characters[&'k'], tally(2, 2));
+        assert_eq!(score.total(), tally(8, 7));
+    }
+
+    #[test]
+    fn wrong_and_extra_characters() {
+        let score = Score::new("kmkm", "kkxkm");
+        assert_eq!(score.total(), tally(4, 3));
+        assert_eq!(score.characters[&'m'], tally(2, 1));
+        assert_eq!(score.extra, 1);
+        assert_eq!(score.accuracy(), 0.6);
+
+        // Typing far more than was sent doesn't pass
+        let score = Score::new("kmmkk mkkmm", "kmxkmkmxkmkmxkm kmxkmkmxkmkkmxm");
+        assert_eq!(score.total(), tally(10, 10));
+        assert_eq!(score.extra, 20);
+        assert!(score.accuracy() < PASS_MARK);
+        assert_eq!(Score::new("km", "xx km").extra, 2);
+
+        let score = Score::new("kmkm", "");
+        assert_eq!(score.total(), tally(4, 0));
+        assert_eq!(Score::new("", "km").accuracy(), 0.0);
+        assert_eq!(Score::new("", "").accuracy(), 0.0);
+    }
+
+    #[test]
+    fn unlocking() {
+        let mut progress = Progress::default();
+        assert_eq!(progress.learnt(), ['k', 'm']);
+
+        assert_eq!(progress.record(&Score::new("kmkmk mkmkm", "kmkmk mkmk")), Some('u'));
+        assert_eq!(progress.learnt(), ['k', 'm', 'u']);
+
+        // 60% isn't enough
+        assert_eq!(progress.record(&Score::new("kmuuk", "kmu")), None);
+        assert_eq!(progress.lesson, 3);
+        assert_eq!(progress.sessions, 2);
+        assert_eq!(progress.characters[&'k'], tally(7, 6));
+    }
+
+    #[test]
+    fn course_ends() {
+        let mut progress = Progress { lesson: KOCH_ORDER.len() - 1, ..Progress::default() };
+        assert_eq!(progress.record(&Score::new("x", "x")), Some('x'));
+        assert!(progress.finished());
+        assert_eq!(progress.record(&Score::new("x", "x")), None);
+        assert_eq!(progress.lesson, KOCH_ORDER.len());
+    }
+
+    #[test]
+    fn groups() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let text = random_groups(&mut rng, &['k', 'm'], 4, 5);
+        assert_eq!(text.len(), 4 * 5 + 3);
+        assert!(text.split(' ').all(|group| group.len() == 5));
+        assert!(text.contains('k') && text.contains('m'));
+        assert!(text.chars().all(|ch| matches!(ch, 'k' | 'm' | ' ')));
+    }
+
+    #[test]
+    fn save_and_load() {
+        let path = std::env::temp_dir().join(format!("koch-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        assert_eq!(Progress::load(&path).unwrap(), Progress::default());
+
+        let mut progress = Progress::default();
+        progress.record(&Score::new("kkmm", "kkmm"));
+        progress.save(&path).unwrap();
+        assert_eq!(Progress::load(&path).unwrap(), progress);
+
+        fs::write(&path, r#"{"lesson": 99, "sessions": 0, "characters": {}}"#).unwrap();
+        assert!(matches!(Progress::load(&path), Err(ProgressError::BadLesson(99))));
+        fs::write(&path, "not json").unwrap();
+        assert!(matches!(Progress::load(&path), Err(ProgressError::Json(_))));
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,9 +1,20 @@
 #![allow(dead_code)]
 
+pub mod audio;
+pub mod codes;
+pub mod koch;
+pub mod listen;
+mod decoder;
+mod encoder;
+
+pub use decoder::{classify, decode_elements, decode_text, decode_timed, DecodeError, KeyEvent};
+pub use codes::Alphabet;
+pub use encoder::{encode, encode_with, render};
+
 /**
 The various possibles things that a Morse transmission can contain.
 */
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Morse {
     Dit,            // 1 time unit on
     Dah,            // 3 dits on
@@ -17,7 +28,7 @@ Return true if given byte is an ascii character transmitable in Morse Code.
 
 Strictly International Morse code (Recommendation ITU-R M.1677-1)
 */
-fn is_morseable(byte: u8) -> bool {
+pub fn is_morseable(byte: u8) -> bool {
     match byte {
         32 => true,                     // Space
         34 => true,                     // "
@@ -35,8 +46,11 @@ fn is_morseable(byte: u8) -> bool {
 
 /**
 String containing only characters that can be sent via MorseCode.
+
+Accented letters, other alphabets, and prosigns are all dropped; see
+`encode_with()` for those.
 */
-fn to_morse(string: &str) -> String {
+pub fn to_morse(string: &str) -> String {
     let lower = string.trim().to_ascii_lowercase();
     let bytes: Vec<u8> = lower
         .bytes()
@@ -0,0 +1,267 @@
+/*!
+Morse code heard in audio, decoded back to text.
+
+The strength of the tone is measured in short, overlapping windows with a
+Goertzel filter, which picks out a single frequency much as one bin of a
+Fourier transform would. That envelope is split into on and off by a
+threshold between the levels of tone and of silence, and the resulting key
+events handed to the timing decoder.
+*/
+
+use std::f64::consts::PI;
+use std::time::Duration;
+
+use crate::decoder::{decode_timed, DecodeError, KeyEvent};
+
+
+/// Length of each window measured, wide enough to pass tones a little off
+/// the expected frequency.
+const WINDOW: f64 = 0.010;
+
+/// Time between the starts of windows.
+const HOP: f64 = 0.0025;
+
+/// Range of tones searched for when the frequency isn't known, in hertz.
+const TONE_RANGE: (f64, f64) = (200.0, 2_000.0);
+
+/// Step between the tones searched for, in hertz.
+const TONE_STEP: f64 = 10.0;
+
+/// Least ratio of tone to background level taken as signal, not noise.
+const MIN_CONTRAST: f64 = 3.0;
+
+
+/**
+Amplitude of a single frequency in the samples, on the same scale as the
+samples themselves.
+*/
+pub fn goertzel(samples: &[i16], frequency: f64, sample_rate: u32) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let coefficient = 2.0 * (2.0 * PI * frequency / f64::from(sample_rate)).cos();
+    let (mut s1, mut s2) = (0.0, 0.0);
+    for &sample in samples {
+        let s = f64::from(sample) + coefficient * s1 - s2;
+        s2 = s1;
+        s1 = s;
+    }
+    let power = s1 * s1 + s2 * s2 - coefficient * s1 * s2;
+    2.0 * power.max(0.0).sqrt() / samples.len() as f64
+}
+
+
+/**
+Frequency of the loudest tone, in hertz, searching the usual range of
+pitches for Morse. Returns `None` for silence.
+*/
+pub fn strongest_tone(samples: &[i16], sample_rate: u32) -> Option<f64> {
+    let window = window_length(sample_rate);
+    let steps = ((TONE_RANGE.1 - TONE_RANGE.0) / TONE_STEP) as usize;
+    let nyquist = f64::from(sample_rate) / 2.0;
+    let (frequency, energy) = (0..=steps)
+        .map(|step| TONE_RANGE.0 + step as f64 * TONE_STEP)
+        .take_while(|&frequency| frequency < nyquist)
+        .map(|frequency| {
+            let energy: f64 = samples
+                .chunks(window)
+                .map(|chunk| goertzel(chunk, frequency, sample_rate).powi(2))
+                .sum();
+            (frequency, energy)
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))?;
+    (energy > 0.0).then_some(frequency)
+}
+
+
+/**
+Level of the tone over time, one value per `HOP` seconds.
+*/
+pub fn envelope(samples: &[i16], frequency: f64, sample_rate: u32) -> Vec<f64> {
+    let window = window_length(sample_rate);
+    let hop = ((HOP * f64::from(sample_rate)).round() as usize).max(1);
+    (0..samples.len().saturating_sub(window) + 1)
+        .step_by(hop)
+        .map(|start| {
+            let end = samples.len().min(start + window);
+            goertzel(&samples[start..end], frequency, sample_rate)
+        })
+        .collect()
+}
+
+
+/**
+Key presses and releases heard in the samples.
+
+The threshold is found by splitting the levels into two clusters, tone
+and background, with a little hysteresis either side of the midpoint so
+noise near it doesn't chatter. If the tone isn't clearly louder than the
+background, there's taken to be no tone at all.
+*/
+pub fn key_events(samples: &[i16], frequency: f64, sample_rate: u32) -> Vec<KeyEvent> {
+    let levels = envelope(samples, frequency, sample_rate);
+    let Some((low, high)) = split_levels(&levels) else {
+        return Vec::new();
+    };
+    if high < MIN_CONTRAST * low {
+        return Vec::new();
+    }
+    let on_above = low + 0.6 * (high - low);
+    let off_below = low + 0.4 * (high - low);
+
+    let hop = (HOP * f64::from(sample_rate)).round().max(1.0) / f64::from(sample_rate);
+    let mut events: Vec<KeyEvent> = Vec::new();
+    let mut on = false;
+    for level in levels {
+        if on && level < off_below {
+            on = false;
+        } else if !on && level > on_above {
+            on = true;
+        }
+        match events.last_mut() {
+            Some(event) if event.on == on => event.duration += Duration::from_secs_f64(hop),
+            _ => events.push(KeyEvent { on, duration: Duration::from_secs_f64(hop) }),
+        }
+    }
+    events
+}
+
+
+/**
+Decode Morse from audio, finding the pitch of the tone if it isn't given.
+*/
+pub fn decode_audio(
+    samples: &[i16],
+    sample_rate: u32,
+    frequency: Option<f64>,
+) -> Result<String, DecodeError> {
+    let Some(frequency) = frequency.or_else(|| strongest_tone(samples, sample_rate)) else {
+        return Ok(String::new());
+    };
+    decode_timed(&key_events(samples, frequency, sample_rate))
+}
+
+
+/**
+Samples in each window.
+*/
+fn window_length(sample_rate: u32) -> usize {
+    ((WINDOW * f64::from(sample_rate)).round() as usize).max(1)
+}
+
+
+/**
+Mean levels of background and tone, splitting the levels in two by
+repeatedly moving the threshold to halfway between the means either side.
+*/
+fn split_levels(levels: &[f64]) -> Option<(f64, f64)> {
+    let min = levels.iter().copied().reduce(f64::min)?;
+    let max = levels.iter().copied().reduce(f64::max)?;
+    let mean = |values: &mut dyn Iterator<Item=&f64>| {
+        let (sum, count) = values.fold((0.0, 0), |(sum, count), value| (sum + value, count + 1));
+        (count > 0).then(|| sum / f64::from(count))
+    };
+
+    let mut threshold = (min + max) / 2.0;
+    let mut means = (min, max);
+    for _ in 0..100 {
+        let low = mean(&mut levels.iter().filter(|&&level| level <= threshold)).unwrap_or(min);
+        let high = mean(&mut levels.iter().filter(|&&level| level > threshold)).unwrap_or(max);
+        means = (low, high);
+        let next = (low + high) / 2.0;
+        if (next - threshold).abs() <= f64::EPSILON * max {
+            break;
+        }
+        threshold = next;
+    }
+    Some(means)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+
+    use crate::audio::{synthesize, Timing};
+    use crate::encode;
+
+    const TEXT: &str = "cq cq de m0abc, qth london = rst 599 k";
+
+    /// Synthesized text with a second of silence either side, and
+    /// roughly Gaussian noise of the given standard deviation.
+    fn noisy(text: &str, timing: &Timing, noise: f64, seed: u64) -> Vec<i16> {
+        let padding = vec![0; timing.sample_rate as usize];
+        let clean = [padding.as_slice(), &synthesize(&encode(text), timing), &padding].concat();
+        let mut rng = SmallRng::seed_from_u64(seed);
+        clean
+            .iter()
+            .map(|&sample| {
+                // Sum of twelve uniforms has a standard deviation of one
+                let gaussian: f64 = (0..12).map(|_| rng.gen::<f64>()).sum::<f64>() - 6.0;
+                (f64::from(sample) + noise * gaussian).round().clamp(-32768.0, 32767.0) as i16
+            })
+            .collect()
+    }
+
+    #[test]
+    fn goertzel_amplitude() {
+        let rate = 8_000;
+        let tone: Vec<i16> = (0..800)
+            .map(|n| (1_000.0 * (2.0 * PI * 600.0 * f64::from(n) / f64::from(rate)).sin()) as i16)
+            .collect();
+        assert!((goertzel(&tone, 600.0, rate) - 1_000.0).abs() < 5.0);
+        assert!(goertzel(&tone, 900.0, rate) < 10.0);
+        assert_eq!(goertzel(&[], 600.0, rate), 0.0);
+    }
+
+    #[test]
+    fn finds_the_tone() {
+        let timing = Timing { frequency: 730.0, sample_rate: 8_000, ..Timing::default() };
+        let samples = noisy("test", &timing, 2_000.0, 1);
+        assert_eq!(strongest_tone(&samples, 8_000), Some(730.0));
+        assert_eq!(strongest_tone(&[0; 8_000], 8_000), None);
+    }
+
+    #[test]
+    fn clean_round_trip() {
+        for wpm in [5.0, 12.0, 20.0, 30.0, 40.0] {
+            let timing = Timing { wpm, farnsworth_wpm: wpm, ..Timing::default() };
+            let samples = synthesize(&encode(TEXT), &timing);
+            assert_eq!(decode_audio(&samples, 44_100, Some(600.0)).unwrap(), TEXT, "{wpm} WPM");
+        }
+    }
+
+    #[test]
+    fn noisy_round_trip() {
+        // Noise at half the tone's peak, and the tone's pitch unknown
+        let timing = Timing { frequency: 650.0, sample_rate: 8_000, ..Timing::default() };
+        for seed in 0..5 {
+            let samples = noisy(TEXT, &timing, 13_000.0, seed);
+            assert_eq!(decode_audio(&samples, 8_000, None).unwrap(), TEXT, "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn farnsworth_round_trip() {
+        let timing = Timing {
+            wpm: 25.0,
+            farnsworth_wpm: 8.0,
+            sample_rate: 11_025,
+            ..Timing::default()
+        };
+        let samples = noisy(TEXT, &timing, 5_000.0, 7);
+        assert_eq!(decode_audio(&samples, 11_025, Some(600.0)).unwrap(), TEXT);
+    }
+
+    #[test]
+    fn noise_alone_is_silence() {
+        let timing = Timing { sample_rate: 8_000, ..Timing::default() };
+        let samples = noisy("", &timing, 5_000.0, 3);
+        assert!(key_events(&samples, 600.0, 8_000).is_empty());
+        assert_eq!(decode_audio(&samples, 8_000, Some(600.0)).unwrap(), "");
+        assert_eq!(decode_audio(&[], 8_000, None).unwrap(), "");
+    }
+}
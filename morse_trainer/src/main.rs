@@ -1,23 +1,200 @@
-#![allow(dead_code)]
-#![allow(unused_imports)]
-#![allow(unused_variables)]
+use clap::{Parser, Subcommand};
 
-use morse_trainer::Morse::{self, *};
+use rand::rngs::SmallRng;
+use rand::SeedableRng;
+
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use morse_trainer::audio::{load_wav, save_wav, Timing};
+use morse_trainer::codes;
+use morse_trainer::koch::{random_groups, Progress, Score, PASS_MARK};
+use morse_trainer::listen::decode_audio;
+use morse_trainer::{decode_text, encode, encode_with, render, Alphabet};
+
+
+/// Encode text as Morse code, or decode dots and dashes back to text
+#[derive(Parser, Debug)]
+#[command(version, about, args_conflicts_with_subcommands=true)]
+struct Args {
+    /// Text to encode, or dots and dashes to decode
+    text: Vec<String>,
+
+    /// Save the encoded text as audio to this WAV file
+    #[arg(long)]
+    wav: Option<PathBuf>,
+
+    /// Extra alphabets to encode with: latin, cyrillic, greek, or wabun
+    #[arg(long, value_delimiter=',')]
+    alphabet: Vec<Alphabet>,
+
+    /// Decode Morse heard in this mono WAV file, instead of taking text
+    #[arg(long, conflicts_with_all=["text", "wav"])]
+    listen: Option<PathBuf>,
+
+    /// Speed of each character, in words per minute
+    #[arg(long, global=true, default_value_t=20.0, value_parser=speed)]
+    wpm: f64,
+
+    /// Slower overall speed, in words per minute, spacing characters out
+    #[arg(long, global=true, value_parser=speed)]
+    farnsworth: Option<f64>,
+
+    /// Pitch of the tone, in hertz. Found by itself when listening.
+    #[arg(long, global=true)]
+    tone: Option<f64>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Learn Morse by the Koch method, copying random groups of characters
+    Train {
+        /// File to keep progress in between sessions
+        #[arg(long, default_value="koch.json")]
+        progress: PathBuf,
+
+        /// Audio for each session is written to this WAV file
+        #[arg(long, default_value="koch.wav")]
+        wav: PathBuf,
+
+        /// Number of groups in each session
+        #[arg(long, default_value_t=20)]
+        groups: usize,
+
+        /// Characters in each group
+        #[arg(long, default_value_t=5)]
+        group_size: usize,
+
+        /// Also show the dots and dashes, for those without audio
+        #[arg(long)]
+        show: bool,
+    },
+}
 
 
 fn main() {
-    let bytes = vec![b's', b't', b'e', b'l', b'l', b'a'];
-    let codes: Vec<Morse> = bytes.iter()
-        .map(|byte| match byte {
-            b'a' => [Dit, Dah],
-            b'b' => [Dah, Dit, Dit, Dit],
-            b'c' => [Dah, Dit, Dah, Dit],
-            b'd' => [Dah, Dit, Dit],
-            b'e' => [Dit],
-            b'f' => [Dit, Dit, Dah, Dit],
-            _ => [],
-        })
-        .flatten()
-        .collect();
+    let args = Args::parse();
+    if !args.tone.is_none_or(|tone| tone > 0.0) {
+        fail("pitch must be positive");
+    }
+    let timing = Timing {
+        frequency: args.tone.unwrap_or(Timing::default().frequency),
+        wpm: args.wpm,
+        farnsworth_wpm: args.farnsworth.unwrap_or(args.wpm).min(args.wpm),
+        ..Timing::default()
+    };
+
+    if let Some(Command::Train { progress, wav, groups, group_size, show }) = &args.command {
+        if let Err(error) = train(&timing, progress, wav, *groups, *group_size, *show) {
+            fail(&error.to_string());
+        }
+        return;
+    }
+
+    if let Some(path) = &args.listen {
+        let wav = load_wav(path)
+            .unwrap_or_else(|error| fail(&format!("couldn't read {}: {error}", path.display())));
+        match decode_audio(&wav.samples, wav.sample_rate, args.tone) {
+            Ok(decoded) => println!("{decoded}"),
+            Err(error) => fail(&error.to_string()),
+        }
+        return;
+    }
+
+    let text = if args.text.is_empty() { "stella".to_string() } else { args.text.join(" ") };
+
+    // Dots and dashes are decoded, anything else encoded
+    if text.chars().all(|c| matches!(c, '.' | '-' | '/' | ' ')) {
+        match decode_text(&text) {
+            Ok(decoded) => println!("{decoded}"),
+            Err(error) => fail(&error.to_string()),
+        }
+        return;
+    }
+
+    let codes = encode_with(&text, &args.alphabet);
     println!("{codes:?}");
+    println!("{}", render(&codes));
+
+    if let Some(path) = args.wav {
+        if let Err(error) = save_wav(&path, &codes, &timing) {
+            fail(&format!("couldn't write {}: {error}", path.display()));
+        }
+        println!("{:.2}s written to {}", timing.duration(&codes).as_secs_f64(), path.display());
+    }
+}
+
+
+/// Run one Koch method session, and save the student's progress.
+fn train(
+    timing: &Timing,
+    progress_path: &Path,
+    wav: &Path,
+    groups: usize,
+    group_size: usize,
+    show: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut progress = Progress::load(progress_path)?;
+    let learnt = progress.learnt();
+    println!("Lesson {}: {}", progress.lesson - 1, learnt.iter().collect::<String>());
+
+    let mut rng = SmallRng::from_entropy();
+    let sent = random_groups(&mut rng, &learnt, groups, group_size);
+    let codes = encode(&sent);
+    save_wav(wav, &codes, timing)?;
+    println!("Play {} ({:.0}s)", wav.display(), timing.duration(&codes).as_secs_f64());
+    if show {
+        println!("{}", render(&codes));
+    }
+    print!("Type what you heard, then press Enter: ");
+    io::stdout().flush()?;
+    let mut copied = String::new();
+    io::stdin().lock().read_line(&mut copied)?;
+
+    let score = Score::new(&sent, &copied);
+    println!("\nSent:   {sent}");
+    println!("Copied: {}", copied.trim());
+    for (ch, tally) in &score.characters {
+        let accuracy = tally.accuracy().unwrap_or(0.0);
+        println!("  {ch}  {:>3}/{:<3} {:>5.1}%", tally.correct, tally.sent, 100.0 * accuracy);
+    }
+    if score.extra > 0 {
+        println!("  {} extra characters copied", score.extra);
+    }
+    println!("Accuracy: {:.1}%", 100.0 * score.accuracy());
+
+    match progress.record(&score) {
+        Some(ch) => {
+            let code = codes::code(ch).unwrap_or_default();
+            println!("Passed! Next session adds {ch}  {code}");
+        },
+        None if progress.finished() => println!("Every character learnt."),
+        None => println!("{:.0}% needed to add a new character.", 100.0 * PASS_MARK),
+    }
+    progress.save(progress_path)?;
+    Ok(())
+}
+
+
+/// Speeds outside this range, in words per minute, are refused, rather than
+/// trying to write hours of audio.
+const SPEEDS: std::ops::RangeInclusive<f64> = 1.0..=100.0;
+
+
+fn speed(text: &str) -> Result<f64, String> {
+    let wpm: f64 = text.parse().map_err(|_| format!("{text:?} isn't a number"))?;
+    if !SPEEDS.contains(&wpm) {
+        return Err(format!("speeds run from {} to {} WPM", SPEEDS.start(), SPEEDS.end()));
+    }
+    Ok(wpm)
+}
+
+
+fn fail(message: &str) -> ! {
+    eprintln!("{message}");
+    std::process::exit(1);
 }
@@ -0,0 +1,402 @@
+//! Board layouts: size, snakes, and ladders.
+//!
+//! Boards are read from TOML or JSON, with each jump given as a `[from, to]`
+//! pair of squares, and are checked before use. A few classic boards are
+//! bundled with the crate, from the `boards` folder.
+//!
+//! ```toml
+//! name = "Tiny"
+//! size = 10
+//! snakes = [[9, 2]]
+//! ladders = [[3, 7]]
+//! ```
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+
+/// Boards bundled with the crate, by name.
+const BUNDLED: [(&str, &str); 4] = [
+    ("chutes_and_ladders", include_str!("../boards/chutes_and_ladders.toml")),
+    ("snakes_and_ladders", include_str!("../boards/snakes_and_ladders.toml")),
+    ("rosetta_code", include_str!("../boards/rosetta_code.toml")),
+    ("plain", include_str!("../boards/plain.toml")),
+];
+
+
+/// Validated board layout.
+///
+/// Players start off the board, on square zero, and must land exactly on
+/// the last square, `size`, to finish. Landing on the start of a snake or
+/// ladder moves the player to its end, following on if that is the start
+/// of another.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "BoardFile", into = "BoardFile")]
+pub struct Board {
+    name: String,
+    size: u8,
+    snakes: Vec<(u8, u8)>,
+    ladders: Vec<(u8, u8)>,
+
+    /// Where a player landing on each square really ends up.
+    destinations: Vec<u8>,
+}
+
+
+/// Board as written in a file, before validation.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct BoardFile {
+    name: String,
+    size: u8,
+    snakes: Vec<(u8, u8)>,
+    ladders: Vec<(u8, u8)>,
+}
+
+
+/// Problems loading or validating a board.
+#[derive(Debug)]
+pub enum BoardError {
+    Io(io::Error),
+    Toml(toml::de::Error),
+    Json(serde_json::Error),
+
+    /// File name extension is neither `.toml` nor `.json`.
+    UnknownFormat,
+
+    /// Board needs at least one square.
+    Empty,
+
+    /// Snake or ladder starts or ends off the board.
+    OutOfRange { from: u8, to: u8 },
+
+    /// Snakes must go down, and ladders up.
+    WrongWay { from: u8, to: u8 },
+
+    /// Nothing may start on the final square, or the game could never end.
+    JumpFromFinal(u8),
+
+    /// More than one snake or ladder starts on the same square.
+    SharedStart(u8),
+
+    /// Snakes and ladders lead from this square back to itself.
+    Cycle(u8),
+
+    /// No sequence of rolls leads from this square to the final square.
+    Unfinishable(u8),
+}
+
+
+impl Board {
+    /// Validate and build a new board.
+    pub fn new(
+        name: &str,
+        size: u8,
+        snakes: &[(u8, u8)],
+        ladders: &[(u8, u8)],
+    ) -> Result<Self, BoardError> {
+        if size == 0 {
+            return Err(BoardError::Empty);
+        }
+
+        let mut jumps: Vec<Option<u8>> = vec![None; usize::from(size) + 1];
+        let all = snakes.iter().map(|jump| (jump, true))
+            .chain(ladders.iter().map(|jump| (jump, false)));
+        for (&(from, to), is_snake) in all {
+            if !(1..=size).contains(&from) || !(1..=size).contains(&to) {
+                return Err(BoardError::OutOfRange { from, to });
+            }
+            if (is_snake && to >= from) || (!is_snake && to <= from) {
+                return Err(BoardError::WrongWay { from, to });
+            }
+            if from == size {
+                return Err(BoardError::JumpFromFinal(from));
+            }
+            if jumps[usize::from(from)].replace(to).is_some() {
+                return Err(BoardError::SharedStart(from));
+            }
+        }
+
+        // Follow chains of jumps to their end, looking for loops
+        let mut destinations = Vec::with_capacity(jumps.len());
+        for square in 0..=size {
+            let mut place = square;
+            let mut steps = 0;
+            while let Some(to) = jumps[usize::from(place)] {
+                place = to;
+                steps += 1;
+                if steps > jumps.len() {
+                    return Err(BoardError::Cycle(square));
+                }
+            }
+            destinations.push(place);
+        }
+
+        // Work back from the final square, marking every square with a roll
+        // leading to one already known to finish, until none are added
+        let size = usize::from(size);
+        let mut finishes = vec![false; size + 1];
+        finishes[size] = true;
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for square in 0..size {
+                let reaches = (square + 1..=size.min(square + 6))
+                    .any(|landed| finishes[usize::from(destinations[landed])]);
+                if reaches && !finishes[square] {
+                    finishes[square] = true;
+                    changed = true;
+                }
+            }
+        }
+        // Report a square players can stop on, not the start of a jump
+        let stranded = (0..size)
+            .find(|&square| !finishes[square] && usize::from(destinations[square]) == square);
+        if let Some(square) = stranded {
+            return Err(BoardError::Unfinishable(square as u8));
+        }
+
+        Ok(Board {
+            name: name.to_string(),
+            size: size as u8,
+            snakes: snakes.to_vec(),
+            ladders: ladders.to_vec(),
+            destinations,
+        })
+    }
+
+    /// Load board from a TOML or JSON file, depending on its extension.
+    pub fn load(path: &Path) -> Result<Self, BoardError> {
+        let text = fs::read_to_string(path).map_err(BoardError::Io)?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Board::from_toml(&text),
+            Some("json") => Board::from_json(&text),
+            _ => Err(BoardError::UnknownFormat),
+        }
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, BoardError> {
+        toml::from_str(text).map_err(BoardError::Toml)
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, BoardError> {
+        serde_json::from_str(text).map_err(BoardError::Json)
+    }
+
+    /// One of the boards bundled with the crate, by name.
+    pub fn bundled(name: &str) -> Option<Self> {
+        let (_, text) = BUNDLED.iter().find(|(bundled, _)| *bundled == name)?;
+        Some(Board::from_toml(text).expect("bundled boards are valid"))
+    }
+
+    /// Names of all the bundled boards.
+    pub fn bundled_names() -> impl Iterator<Item=&'static str> {
+        BUNDLED.iter().map(|(name, _)| *name)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Number of the final square.
+    pub fn size(&self) -> u8 {
+        self.size
+    }
+
+    pub fn snakes(&self) -> &[(u8, u8)] {
+        &self.snakes
+    }
+
+    pub fn ladders(&self) -> &[(u8, u8)] {
+        &self.ladders
+    }
+
+    /// Where a player landing on `square` ends up, after any snakes and
+    /// ladders.
+    ///
+    /// Panics if `square` is off the board.
+    pub fn destination(&self, square: u8) -> u8 {
+        self.destinations[usize::from(square)]
+    }
+}
+
+
+/// The classic Chutes and Ladders board.
+impl Default for Board {
+    fn default() -> Self {
+        Board::bundled("chutes_and_ladders").unwrap()
+    }
+}
+
+
+impl TryFrom<BoardFile> for Board {
+    type Error = BoardError;
+
+    fn try_from(file: BoardFile) -> Result<Self, BoardError> {
+        Board::new(&file.name, file.size, &file.snakes, &file.ladders)
+    }
+}
+
+
+impl From<Board> for BoardFile {
+    fn from(board: Board) -> Self {
+        BoardFile {
+            name: board.name,
+            size: board.size,
+            snakes: board.snakes,
+            ladders: board.ladders,
+        }
+    }
+}
+
+
+impl fmt::Display for BoardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BoardError::Io(error) => write!(f, "{error}"),
+            BoardError::Toml(error) => write!(f, "{error}"),
+            BoardError::Json(error) => write!(f, "{error}"),
+            BoardError::UnknownFormat => write!(f, "board files must be .toml or .json"),
+            BoardError::Empty => write!(f, "board has no squares"),
+            BoardError::OutOfRange { from, to } => {
+                write!(f, "jump from {from} to {to} leaves the board")
+            },
+            BoardError::WrongWay { from, to } => {
+                write!(f, "jump from {from} to {to} goes the wrong way")
+            },
+            BoardError::JumpFromFinal(square) => {
+                write!(f, "jump from the final square, {square}")
+            },
+            BoardError::SharedStart(square) => {
+                write!(f, "more than one jump from square {square}")
+            },
+            BoardError::Cycle(square) => write!(f, "jumps from square {square} loop forever"),
+            BoardError::Unfinishable(square) => {
+                write!(f, "the final square can't be reached from square {square}")
+            },
+        }
+    }
+}
+
+impl std::error::Error for BoardError {}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_boards_are_valid() {
+        for name in Board::bundled_names() {
+            assert!(Board::bundled(name).is_some(), "{name}");
+        }
+        assert!(Board::bundled("monopoly").is_none());
+    }
+
+    #[test]
+    fn chutes_and_ladders() {
+        let board = Board::default();
+        assert_eq!(board.name(), "Chutes and Ladders");
+        assert_eq!(board.size(), 100);
+        assert_eq!(board.snakes().len(), 10);
+        assert_eq!(board.ladders().len(), 9);
+        assert_eq!(board.destination(0), 0);
+        assert_eq!(board.destination(1), 38);
+        assert_eq!(board.destination(2), 2);
+        assert_eq!(board.destination(87), 24);
+        assert_eq!(board.destination(100), 100);
+    }
+
+    #[test]
+    fn other_classic_boards() {
+        let board = Board::bundled("snakes_and_ladders").unwrap();
+        assert_eq!((board.snakes().len(), board.ladders().len()), (10, 11));
+        assert_eq!(board.destination(2), 38);
+        assert_eq!(board.destination(99), 80);
+
+        let board = Board::bundled("rosetta_code").unwrap();
+        assert_eq!((board.snakes().len(), board.ladders().len()), (8, 8));
+        assert_eq!(board.destination(17), 7);
+        assert_eq!(board.destination(63), 81);
+    }
+
+    #[test]
+    fn from_json() {
+        let json = r#"{
+            "name": "Tiny",
+            "size": 10,
+            "snakes": [[9, 2]],
+            "ladders": [[3, 7]]
+        }"#;
+        let board = Board::from_json(json).unwrap();
+        assert_eq!(board.destination(9), 2);
+        assert_eq!(board.destination(3), 7);
+    }
+
+    #[test]
+    fn round_trip() {
+        let board = Board::default();
+        let json = serde_json::to_string(&board).unwrap();
+        assert_eq!(Board::from_json(&json).unwrap(), board);
+        let toml = toml::to_string(&board).unwrap();
+        assert_eq!(Board::from_toml(&toml).unwrap(), board);
+    }
+
+    #[test]
+    fn chained_jumps() {
+        let board = Board::new("Chain", 20, &[(15, 2)], &[(5, 15)]).unwrap();
+        assert_eq!(board.destination(5), 2);
+    }
+
+    #[test]
+    fn invalid_boards() {
+        let invalid = |size, snakes: &[(u8, u8)], ladders: &[(u8, u8)]| {
+            Board::new("Bad", size, snakes, ladders).unwrap_err()
+        };
+        assert!(matches!(invalid(0, &[], &[]), BoardError::Empty));
+        assert!(matches!(
+            invalid(10, &[(11, 2)], &[]),
+            BoardError::OutOfRange { from: 11, to: 2 },
+        ));
+        assert!(matches!(
+            invalid(10, &[], &[(0, 5)]),
+            BoardError::OutOfRange { from: 0, to: 5 },
+        ));
+        assert!(matches!(
+            invalid(10, &[(2, 8)], &[]),
+            BoardError::WrongWay { from: 2, to: 8 },
+        ));
+        assert!(matches!(invalid(10, &[(10, 1)], &[]), BoardError::JumpFromFinal(10)));
+        assert!(matches!(invalid(10, &[(5, 1)], &[(5, 9)]), BoardError::SharedStart(5)));
+        assert!(matches!(invalid(10, &[(8, 3)], &[(3, 8)]), BoardError::Cycle(3)));
+    }
+
+    #[test]
+    fn unfinishable_boards() {
+        let invalid = |snakes: &[(u8, u8)], ladders: &[(u8, u8)]| {
+            Board::new("Trap", 20, snakes, ladders).unwrap_err()
+        };
+
+        // Every roll from the start hits a snake back to square one
+        let snakes: Vec<(u8, u8)> = (2..=7).map(|square| (square, 1)).collect();
+        assert!(matches!(invalid(&snakes, &[]), BoardError::Unfinishable(0)));
+
+        // The start can reach the finish by the ladder, but a roll of six
+        // strands the player below a wall of snakes
+        let snakes: Vec<(u8, u8)> = (11..=16).map(|square| (square, 10)).collect();
+        assert!(matches!(invalid(&snakes, &[(5, 18)]), BoardError::Unfinishable(6)));
+
+        // Only an exact roll finishes, but one always can
+        assert!(Board::new("Snaky", 20, &[(19, 1), (18, 1), (17, 1)], &[]).is_ok());
+    }
+
+    #[test]
+    fn validated_when_parsed() {
+        let error = Board::from_toml("name = 'Bad'\nsize = 10\nsnakes = [[10, 1]]\nladders = []")
+            .unwrap_err();
+        assert!(error.to_string().contains("final square"), "{error}");
+    }
+}
@@ -1,36 +1,91 @@
 
 use std::collections::HashMap;
-use std::time::Duration;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 use rand::RngCore;
 use rand::rngs::SmallRng;
 use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+mod board;
+mod markov;
+mod multiplayer;
+mod render;
+
+pub use board::{Board, BoardError};
+pub use markov::MarkovChain;
+pub use multiplayer::{play_multiplayer, MultiplayerResult, Overshoot, Player, Rules};
+pub use render::ROW_LENGTH;
 
 
 /// What a player rolled, and where they ended up
-#[derive(Debug)]
-struct Turn {
-    roll: u8,
-    result: u8,
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Turn {
+    pub roll: u8,
+    pub result: u8,
+}
+
+
+impl Turn {
+    /// Describe this turn, taken from square `from`, eg. a roll of one from
+    /// square 35 reads `rolled 1:  35 ->  44 (ladder from 36)`.
+    pub fn describe(&self, board: &Board, from: u8) -> String {
+        let landed = u16::from(from) + u16::from(self.roll);
+        let note = if landed > u16::from(board.size()) {
+            " (too high, stay)".to_string()
+        } else if u16::from(self.result) > landed {
+            format!(" (ladder from {landed})")
+        } else if u16::from(self.result) < landed {
+            format!(" (snake from {landed})")
+        } else {
+            String::new()
+        };
+        format!("rolled {}: {:>3} -> {:>3}{}", self.roll, from, self.result, note)
+    }
 }
 
 
 /// Entire game history.
-/// Last turn must be an exact roll to land on 100.
-#[derive(Debug)]
-struct Game {
+/// Last turn must be an exact roll to land on the final square.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Game {
     turns: Vec<Turn>,
 }
 
 
 impl Game {
-    fn new() -> Self {
+    pub fn new() -> Self {
         Self {
             turns: Vec::new(),
         }
     }
 
-    fn add_turn(&mut self, roll: u8, result: u8) {
+    pub fn turns(&self) -> &[Turn] {
+        &self.turns
+    }
+
+    /// Number of turns taken.
+    pub fn len(&self) -> usize {
+        self.turns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.turns.is_empty()
+    }
+
+    /// Describe every turn of the game, one per line.
+    pub fn replay(&self, board: &Board) -> String {
+        let mut text = String::new();
+        let mut place = 0;
+        for (i, turn) in self.turns.iter().enumerate() {
+            text.push_str(&format!("{:>4}. {}\n", i + 1, turn.describe(board, place)));
+            place = turn.result;
+        }
+        text
+    }
+
+    pub fn add_turn(&mut self, roll: u8, result: u8) {
         self.turns.push(Turn { roll, result });
     }
 
@@ -40,8 +95,16 @@ impl Game {
 }
 
 
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
 /// Aggregate results from many, many games
-struct BenchmarkResult {
+#[derive(Debug)]
+pub struct BenchmarkResult {
     elapsed: Duration,
     num_games: usize,
 
@@ -54,27 +117,261 @@ struct BenchmarkResult {
 }
 
 
-pub fn play_games(num_games: usize) -> usize {
+impl BenchmarkResult {
+    fn empty() -> Self {
+        BenchmarkResult {
+            elapsed: Duration::ZERO,
+            num_games: 0,
+            counts: HashMap::new(),
+            shortest: Game::new(),
+            longest: Game::new(),
+        }
+    }
+
+    /// Add results of more games. On ties, the games already kept as the
+    /// shortest and longest win.
+    fn merge(&mut self, other: BenchmarkResult) {
+        self.elapsed += other.elapsed;
+        self.num_games += other.num_games;
+        for (length, count) in other.counts {
+            *self.counts.entry(length).or_insert(0) += count;
+        }
+        if self.shortest.is_empty() || other.shortest.len() < self.shortest.len() {
+            self.shortest = other.shortest;
+        }
+        if other.longest.len() > self.longest.len() {
+            self.longest = other.longest;
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    pub fn num_games(&self) -> usize {
+        self.num_games
+    }
+
+    /// Number of games of each length, in rolls.
+    pub fn counts(&self) -> &HashMap<usize, usize> {
+        &self.counts
+    }
+
+    pub fn shortest(&self) -> &Game {
+        &self.shortest
+    }
+
+    pub fn longest(&self) -> &Game {
+        &self.longest
+    }
+
+    /// Average number of rolls per game.
+    pub fn mean(&self) -> f64 {
+        let total: usize = self.counts.iter().map(|(length, count)| length * count).sum();
+        total as f64 / self.num_games as f64
+    }
+
+    pub fn median(&self) -> usize {
+        self.percentile(50.0)
+    }
+
+    /// Shortest game length which at least `percent` of games were
+    /// finished within.
+    pub fn percentile(&self, percent: f64) -> usize {
+        let target = (percent / 100.0 * self.num_games as f64).ceil().max(1.0) as usize;
+        let mut seen = 0;
+        for (length, count) in self.sorted_counts() {
+            seen += count;
+            if seen >= target {
+                return length;
+            }
+        }
+        self.longest.len()
+    }
+
+    /// Bar chart of game lengths, up to the 99th percentile, with one last
+    /// row for all the longer games. The tallest bar is `width` wide.
+    pub fn histogram(&self, width: usize) -> String {
+        let cutoff = self.percentile(99.0);
+        let mut rows: Vec<(String, usize)> = Vec::new();
+        let mut longer = 0;
+        for (length, count) in self.sorted_counts() {
+            if length <= cutoff {
+                rows.push((length.to_string(), count));
+            } else {
+                longer += count;
+            }
+        }
+        if longer > 0 {
+            rows.push((format!("{}+", cutoff + 1), longer));
+        }
+
+        let highest = rows.iter().map(|&(_, count)| count).max().unwrap_or(1);
+        let label_width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+        let mut text = String::new();
+        for (label, count) in rows {
+            let percent = count as f64 / self.num_games as f64 * 100.0;
+            let bar = "#".repeat((count * width).div_ceil(highest));
+            text.push_str(&format!("{label:>label_width$} {percent:>6.2}% {bar}\n"));
+        }
+        text
+    }
+
+    /// Game lengths and their counts, shortest first.
+    fn sorted_counts(&self) -> Vec<(usize, usize)> {
+        let mut counts: Vec<_> = self.counts.iter().map(|(&length, &count)| (length, count)).collect();
+        counts.sort_unstable();
+        counts
+    }
+}
+
+
+/// Games played by each work-unit.
+const BATCH_SIZE: usize = 10_000;
+
+/// Rolls after which a game is abandoned. Far longer than any game on a
+/// real board, but boards can be built whose games go on for ages.
+const MAX_ROLLS: usize = 100_000;
+
+/// Rolls between checks of the time limit, during a single game.
+const ROLLS_PER_CHECK: usize = 1_024;
+
+
+/// Play many games across all cores, keeping statistics on their lengths.
+///
+/// Stops after `num_games` games, or once `time_limit` has passed, whichever
+/// comes first. Games cut short by the time limit, or longer than
+/// `MAX_ROLLS`, aren't counted. Given a `seed`, runs which play the same
+/// number of games give identical results however many threads they use.
+pub fn play_games(
+    board: &Board,
+    num_games: usize,
+    time_limit: Duration,
+    seed: Option<u64>,
+) -> BenchmarkResult {
+    let timer = Instant::now();
+    let batches = run_batches(num_games, time_limit, seed, |rng, size, deadline| {
+        play_games_with(rng, board, size, deadline)
+    });
+    let mut result = BenchmarkResult::empty();
+    for batch in batches {
+        result.merge(batch);
+    }
+    result.elapsed = timer.elapsed();
+    result
+}
+
+
+/// Share games out between all cores, in batches, returning the result of
+/// every batch played, in order.
+///
+/// Each batch has its own `SmallRng` seeded from a master generator, so
+/// with a `seed` batches always get the same seeds. Batches are no longer
+/// started once `time_limit` has passed, and `play` is given the deadline
+/// so it can stop part way through a batch.
+fn run_batches<T, F>(num_games: usize, time_limit: Duration, seed: Option<u64>, play: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(&mut SmallRng, usize, Instant) -> T + Sync,
+{
     // Use strong default RNG to seed faster non-cryptographic generator.
     // We can then create multiple small RNGs, one per work-unit.
-    let mut thread_rng = rand::thread_rng();
-    let mut rng = SmallRng::from_rng(&mut thread_rng).unwrap();
+    let master = match seed {
+        Some(seed) => SmallRng::seed_from_u64(seed),
+        None => SmallRng::from_rng(rand::thread_rng()).unwrap(),
+    };
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+
+    // Index of, and seed for, the next batch to be played
+    let next_batch = Mutex::new((0, master));
+
+    // Limits beyond a century or so are taken as no limit, rather than
+    // overflowing `Instant`
+    let deadline = Instant::now() + time_limit.min(Duration::from_secs(u64::from(u32::MAX)));
+    let claim = || {
+        if Instant::now() >= deadline {
+            return None;
+        }
+        let mut next = next_batch.lock().unwrap();
+        let (index, master) = &mut *next;
+        let start = *index * BATCH_SIZE;
+        if start >= num_games {
+            return None;
+        }
+        let batch = *index;
+        *index += 1;
+        let size = BATCH_SIZE.min(num_games - start);
+        Some((batch, size, SmallRng::seed_from_u64(master.next_u64())))
+    };
+
+    let mut batches: Vec<(usize, T)> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| scope.spawn(|| {
+                let mut results = Vec::new();
+                while let Some((index, size, mut rng)) = claim() {
+                    results.push((index, play(&mut rng, size, deadline)));
+                }
+                results
+            }))
+            .collect();
+        workers.into_iter().flat_map(|worker| worker.join().unwrap()).collect()
+    });
 
+    batches.sort_unstable_by_key(|&(index, _)| index);
+    batches.into_iter().map(|(_, result)| result).collect()
+}
+
+
+/// Play up to `num_games` games, stopping early at the `deadline`.
+fn play_games_with(
+    rng: &mut SmallRng,
+    board: &Board,
+    num_games: usize,
+    deadline: Instant,
+) -> BenchmarkResult {
+    let timer = Instant::now();
+    let mut finished = 0;
+    let mut counts = HashMap::new();
+    let mut shortest = Game::new();
+    let mut longest = Game::new();
     let mut game = Game::new();
-    let mut num_rolls = 0;
     for _ in 1..=num_games {
-        num_rolls = play_game(&mut rng, &mut game);
+        if Instant::now() >= deadline {
+            break;
+        }
         game.clear();
+        let Some(num_rolls) = play_game(rng, board, &mut game, deadline) else {
+            continue;
+        };
+        finished += 1;
+        *counts.entry(num_rolls).or_insert(0) += 1;
+        if shortest.is_empty() || num_rolls < shortest.len() {
+            shortest.clone_from(&game);
+        }
+        if num_rolls > longest.len() {
+            longest.clone_from(&game);
+        }
     }
 
-    dbg!(game.turns.capacity());
-
-    num_rolls
+    BenchmarkResult {
+        elapsed: timer.elapsed(),
+        num_games: finished,
+        counts,
+        shortest,
+        longest,
+    }
 }
 
 
-/// Play a single game of Snakes and Ladders solitaire
-fn play_game(rng: &mut SmallRng, game: &mut Game) -> usize {
+/// Play a single game of Snakes and Ladders solitaire, returning the number
+/// of rolls taken, or `None` if the game was given up after `MAX_ROLLS`, or
+/// because the `deadline` passed.
+fn play_game(
+    rng: &mut SmallRng,
+    board: &Board,
+    game: &mut Game,
+    deadline: Instant,
+) -> Option<usize> {
     let mut num_rolls: usize = 0;
     let mut place: u8 = 0;
 
@@ -85,45 +382,168 @@ fn play_game(rng: &mut SmallRng, game: &mut Game) -> usize {
         num_rolls += 1;
 
         // Where did you end up?
-        let landed = place + roll;
+        let landed = u16::from(place) + u16::from(roll);
 
         // Where did you *really* end up?
-        place = match landed {
-            // Ladders
-            1 => 38,
-            4 => 14,
-            9 => 31,
-            21 => 42,
-            28 => 84,
-            36 => 44,
-            51 => 67,
-            71 => 91,
-            80 => 100,
-
-            // Snakes
-            98 => 78,
-            95 => 75,
-            93 => 73,
-            87 => 24,
-            64 => 60,
-            62 => 19,
-            56 => 53,
-            49 => 11,
-            48 => 26,
-            16 => 6,
-
+        place = if landed > u16::from(board.size()) {
             // Too high? Stay where you are.
-            n if n > 100 => place,
-
-            // Normal move
-            _ => landed,
+            place
+        } else {
+            // Follow any snakes and ladders
+            board.destination(landed as u8)
         };
 
         // Save turn
         game.add_turn(roll, place);
 
-        if place == 100 { break; }
+        if place == board.size() { break; }
+
+        // Give up on endless games
+        if num_rolls >= MAX_ROLLS
+            || (num_rolls.is_multiple_of(ROLLS_PER_CHECK) && Instant::now() >= deadline)
+        {
+            return None;
+        }
     };
 
-    num_rolls
+    Some(num_rolls)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn game_ends_on_final_square() {
+        let board = Board::new("Short", 12, &[(11, 1)], &[(2, 10)]).unwrap();
+        let mut rng = SmallRng::seed_from_u64(1);
+        let mut game = Game::new();
+        for _ in 0..100 {
+            let num_rolls = play_game(&mut rng, &board, &mut game, far_future()).unwrap();
+            assert_eq!(game.turns.len(), num_rolls);
+            assert_eq!(game.turns.last().unwrap().result, 12);
+            assert!(game.turns.iter().all(|turn| turn.result <= 12 && turn.result != 11));
+            game.clear();
+        }
+    }
+
+    #[test]
+    fn benchmark_statistics() {
+        let board = Board::default();
+        let mut rng = SmallRng::seed_from_u64(42);
+        let result = play_games_with(&mut rng, &board, 10_000, far_future());
+        assert_eq!(result.num_games(), 10_000);
+        assert_eq!(result.counts().values().sum::<usize>(), 10_000);
+
+        // Known expected game length on this board is about 39.6 rolls
+        assert!((result.mean() - 39.6).abs() < 1.5, "mean {}", result.mean());
+
+        let shortest = result.shortest().len();
+        let longest = result.longest().len();
+        assert_eq!(result.counts().keys().min(), Some(&shortest));
+        assert_eq!(result.counts().keys().max(), Some(&longest));
+        assert_eq!(result.percentile(0.0), shortest);
+        assert_eq!(result.percentile(100.0), longest);
+        assert!(shortest <= result.median() && result.median() <= longest);
+    }
+
+    #[test]
+    fn seeded_runs_match() {
+        let board = Board::default();
+        let forever = Duration::from_secs(3600);
+        let a = play_games(&board, 45_000, forever, Some(7));
+        let b = play_games(&board, 45_000, forever, Some(7));
+        assert_eq!(a.num_games(), 45_000);
+        assert_eq!(a.counts(), b.counts());
+        assert_eq!(a.shortest(), b.shortest());
+        assert_eq!(a.longest(), b.longest());
+
+        // Same seeds as a single batch played by hand
+        let mut rng = SmallRng::seed_from_u64(7);
+        let mut rng = SmallRng::seed_from_u64(rng.next_u64());
+        let single = play_games_with(&mut rng, &board, BATCH_SIZE, far_future());
+        let c = play_games(&board, BATCH_SIZE, forever, Some(7));
+        assert_eq!(c.counts(), single.counts());
+    }
+
+    pub(crate) fn far_future() -> Instant {
+        Instant::now() + Duration::from_secs(3600)
+    }
+
+    /// Board where every stretch of twenty squares starts with a gate: five
+    /// snakes back to square one, passed only by rolling a six from just
+    /// below them. Finishable, but games take millions of rolls.
+    pub(crate) fn gated_board() -> Board {
+        let snakes: Vec<(u8, u8)> = (0..5)
+            .flat_map(|gate| (11..=15).map(move |square| (gate * 20 + square, 1)))
+            .collect();
+        Board::new("Gates", 100, &snakes, &[]).unwrap()
+    }
+
+    #[test]
+    fn time_limit() {
+        let board = Board::default();
+        let result = play_games(&board, usize::MAX, Duration::from_millis(50), None);
+        assert!(result.num_games() > 0);
+        assert!(result.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn endless_games_are_abandoned() {
+        let board = gated_board();
+        let mut rng = SmallRng::seed_from_u64(1);
+        let mut game = Game::new();
+        assert_eq!(play_game(&mut rng, &board, &mut game, far_future()), None);
+        assert_eq!(game.len(), MAX_ROLLS);
+
+        // A deadline already passed stops the game at the first check
+        game.clear();
+        assert_eq!(play_game(&mut rng, &board, &mut game, Instant::now()), None);
+        assert_eq!(game.len(), ROLLS_PER_CHECK);
+    }
+
+    #[test]
+    fn time_limit_stops_part_way_through_a_batch() {
+        let board = gated_board();
+        let timer = Instant::now();
+        let result = play_games(&board, usize::MAX, Duration::from_millis(50), Some(1));
+        assert!(timer.elapsed() < Duration::from_secs(5));
+        assert!(result.num_games() < BATCH_SIZE);
+    }
+
+    #[test]
+    fn percentiles() {
+        let counts = HashMap::from([(10, 1), (20, 2), (30, 1)]);
+        let result = BenchmarkResult {
+            elapsed: Duration::ZERO,
+            num_games: 4,
+            counts,
+            shortest: Game::new(),
+            longest: Game::new(),
+        };
+        assert_eq!(result.mean(), 20.0);
+        assert_eq!(result.percentile(25.0), 10);
+        assert_eq!(result.median(), 20);
+        assert_eq!(result.percentile(75.0), 20);
+        assert_eq!(result.percentile(76.0), 30);
+    }
+
+    #[test]
+    fn replay() {
+        let board = Board::new("Short", 12, &[(11, 1)], &[(2, 10)]).unwrap();
+        let mut game = Game::new();
+        game.add_turn(2, 10);
+        game.add_turn(1, 1);
+        game.add_turn(6, 7);
+        game.add_turn(6, 7);
+        game.add_turn(5, 12);
+        assert_eq!(game.replay(&board), concat!(
+            "   1. rolled 2:   0 ->  10 (ladder from 2)\n",
+            "   2. rolled 1:  10 ->   1 (snake from 11)\n",
+            "   3. rolled 6:   1 ->   7\n",
+            "   4. rolled 6:   7 ->   7 (too high, stay)\n",
+            "   5. rolled 5:   7 ->  12\n",
+        ));
+    }
 }
@@ -1,9 +1,16 @@
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 
-use std::time::Instant;
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
 
-use snakes_and_ladders;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use snakes_and_ladders::{play_multiplayer, Board, Game, MarkovChain, Player, Rules};
 
 
 /// Play many, many solo games of Snakes and Ladders
@@ -14,21 +21,253 @@ struct Args {
     #[arg(short, long, default_value_t=10_000_000)]
     num_games: usize,
 
-    /// Run for at least this many seconds
+    /// Stop after this many seconds, even if fewer games have been played
     #[arg(short, long, default_value_t=10)]
-    seconds: usize,
+    seconds: u64,
+
+    /// Seed the random number generator, for repeatable results
+    #[arg(long, global=true)]
+    seed: Option<u64>,
+
+    /// Name of a bundled board, or path to a TOML or JSON board file
+    #[arg(short, long, global=true, default_value="chutes_and_ladders")]
+    board: String,
+
+    /// Number of players. With more than one, compare every set of rules.
+    #[arg(short, long, global=true, default_value_t=1)]
+    players: usize,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Play a game on the terminal, pressing Enter to roll
+    Play {
+        /// Save the finished game to this JSON file
+        #[arg(long)]
+        save: Option<PathBuf>,
+
+        /// Instead of playing, replay a game saved with --save
+        #[arg(long)]
+        replay: Option<PathBuf>,
+
+        /// Pause between moves of a replay, in milliseconds
+        #[arg(long, default_value_t=700)]
+        delay: u64,
+    },
 }
 
 
 fn main() {
     let args = Args::parse();
+
+    let board = match load_board(&args.board) {
+        Ok(board) => board,
+        Err(error) => {
+            eprintln!("Could not load board {:?}: {}", args.board, error);
+            let names: Vec<_> = Board::bundled_names().collect();
+            eprintln!("Bundled boards are: {}", names.join(", "));
+            std::process::exit(1);
+        },
+    };
+
+    if let Some(Command::Play { save, replay, delay }) = &args.command {
+        let result = match replay {
+            Some(path) => replay_game(&board, path, Duration::from_millis(*delay)),
+            None => play(&board, &args, save.as_deref()),
+        };
+        if let Err(error) = result {
+            eprintln!("{error}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
     println!("{:?}", args);
+    println!("Playing on {} ({} squares)", board.name(), board.size());
+    if args.players > 1 {
+        compare_rules(&board, &args);
+    } else {
+        solitaire(&board, &args);
+    }
+}
+
 
-    let timer = Instant::now();
-    let num_games: usize = 1_000_000;
-    let num_rolls = snakes_and_ladders::play_games(num_games);
-    let elapsed = timer.elapsed();
+/// Statistics and replays of solitaire games.
+fn solitaire(board: &Board, args: &Args) {
+    let time_limit = Duration::from_secs(args.seconds);
+    let result = snakes_and_ladders::play_games(board, args.num_games, time_limit, args.seed);
+    let num_games = result.num_games();
+    if num_games == 0 {
+        println!("No games played");
+        return;
+    }
+    let elapsed = result.elapsed();
     println!("Played {} games in {:?}", num_games, elapsed);
     println!("Games for second {}", num_games as f64 / elapsed.as_secs_f64());
-    println!("Finished last game in {} rolls", num_rolls);
+    println!();
+
+    println!("Game lengths, in rolls:");
+    print!("{}", result.histogram(60));
+    println!();
+    println!("Mean:   {:.2}", result.mean());
+    let expected = MarkovChain::new(board)
+        .expected_rolls()
+        .expect("boards are checked to be finishable when loaded");
+    println!("Exact:  {:.4}", expected);
+    println!("Median: {}", result.median());
+    for percent in [10.0, 25.0, 75.0, 90.0, 99.0, 99.9] {
+        println!("{:>5}th percentile: {}", percent, result.percentile(percent));
+    }
+    println!();
+
+    println!("Shortest game, {} rolls:", result.shortest().len());
+    print!("{}", result.shortest().replay(board));
+    println!();
+    println!("Longest game, {} rolls:", result.longest().len());
+    print!("{}", result.longest().replay(board));
+}
+
+
+/// Win probability by seat, under every combination of rules.
+///
+/// The time limit is shared evenly between the sets of rules.
+fn compare_rules(board: &Board, args: &Args) {
+    let all = Rules::all();
+    let time_limit = Duration::from_secs(args.seconds) / all.len() as u32;
+    let width = all.iter().map(|rules| rules.to_string().len()).max().unwrap_or(0);
+    let seats: String = (1..=args.players).map(|seat| format!("  Seat {seat:<2}")).collect();
+    println!();
+    println!("{:<width$}{seats}  Rolls/game     Games", "Rules");
+    for rules in all {
+        let result = play_multiplayer(
+            board,
+            rules,
+            args.players,
+            args.num_games,
+            time_limit,
+            args.seed,
+        );
+        if result.num_games() == 0 {
+            println!("{:<width$}  no games played", rules.to_string());
+            continue;
+        }
+        let wins: String = (0..args.players)
+            .map(|seat| format!("  {:>6.2}%", result.win_probability(seat) * 100.0))
+            .collect();
+        println!(
+            "{:<width$}{wins}  {:>10.2}  {:>8}",
+            rules.to_string(),
+            result.mean_rolls(),
+            result.num_games(),
+        );
+    }
+}
+
+
+/// Interactive game, one roll per press of Enter.
+fn play(board: &Board, args: &Args, save: Option<&Path>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut rng = match args.seed {
+        Some(seed) => SmallRng::seed_from_u64(seed),
+        None => SmallRng::from_entropy(),
+    };
+    let rules = Rules::default();
+    let mut players = vec![Player::default(); args.players.max(1)];
+    let mut games = vec![Game::new(); players.len()];
+    let mut message = String::new();
+    let mut lines = io::stdin().lock().lines();
+
+    'game: loop {
+        for seat in 0..players.len() {
+            let places: Vec<u8> = players.iter().map(|player| player.place).collect();
+            draw(board, &places, &message);
+            print!("Player {}, press Enter to roll (q to quit) ", seat_name(seat));
+            io::stdout().flush()?;
+            match lines.next().transpose()? {
+                Some(line) if line.trim() != "q" => {},
+                _ => break 'game,
+            }
+
+            let player = &mut players[seat];
+            let from = player.place;
+            let roll = rng.gen_range(1..=6);
+            player.play_roll(board, &rules, roll);
+            games[seat].add_turn(roll, player.place);
+            let turn = games[seat].turns().last().unwrap();
+            message = format!("Player {} {}", seat_name(seat), turn.describe(board, from));
+
+            if player.place == board.size() {
+                let places: Vec<u8> = players.iter().map(|player| player.place).collect();
+                draw(board, &places, &message);
+                println!("Player {} wins in {} turns!", seat_name(seat), games[seat].len());
+                break 'game;
+            }
+        }
+    }
+
+    if let Some(path) = save {
+        fs::write(path, serde_json::to_string_pretty(&games)?)?;
+        println!("Saved game to {}", path.display());
+    }
+    Ok(())
+}
+
+
+/// Animate a saved game, taking turns in seat order.
+///
+/// The file may hold a single `Game`, or a list of them, one per player.
+fn replay_game(board: &Board, path: &Path, delay: Duration) -> Result<(), Box<dyn std::error::Error>> {
+    let text = fs::read_to_string(path)?;
+    let games: Vec<Game> = match serde_json::from_str(&text) {
+        Ok(games) => games,
+        Err(_) => vec![serde_json::from_str(&text)?],
+    };
+
+    let mut places = vec![0_u8; games.len()];
+    draw(board, &places, "");
+    let longest = games.iter().map(Game::len).max().unwrap_or(0);
+    for i in 0..longest {
+        for (seat, game) in games.iter().enumerate() {
+            let Some(turn) = game.turns().get(i) else { continue };
+            if usize::from(turn.result) > usize::from(board.size()) {
+                return Err(format!("square {} is off the board", turn.result).into());
+            }
+            thread::sleep(delay);
+            let message = format!("Player {} {}", seat_name(seat), turn.describe(board, places[seat]));
+            places[seat] = turn.result;
+            draw(board, &places, &message);
+        }
+    }
+    Ok(())
+}
+
+
+/// Clear the terminal, then draw board, key, and latest move.
+fn draw(board: &Board, places: &[u8], message: &str) {
+    print!("\x1b[2J\x1b[H");
+    println!("{}", board.name());
+    println!();
+    print!("{}", board.render(places));
+    println!();
+    print!("{}", board.legend());
+    println!();
+    println!("{message}");
+}
+
+
+/// Players are known by letter, as marked on the board.
+fn seat_name(seat: usize) -> char {
+    char::from(b'A' + (seat % 26) as u8)
+}
+
+
+/// Find bundled board by name, otherwise load it from a file.
+fn load_board(name: &str) -> Result<Board, snakes_and_ladders::BoardError> {
+    match Board::bundled(name) {
+        Some(board) => Ok(board),
+        None => Board::load(Path::new(name)),
+    }
 }
@@ -0,0 +1,204 @@
+//! Exact analysis of solitaire games as an absorbing Markov chain.
+//!
+//! Each square is a state, and each roll moves the player between states
+//! with probability one sixth per face, following the same rules as
+//! `play_game`: snakes and ladders are followed, and a roll that would go
+//! past the final square leaves the player where they are. The final
+//! square is the only absorbing state.
+
+use crate::Board;
+
+
+/// Transition matrix for a single player on a board.
+#[derive(Clone, Debug)]
+pub struct MarkovChain {
+    /// Probability of moving from square `row` to square `column` in one
+    /// roll, for squares zero up to and including the last.
+    matrix: Vec<Vec<f64>>,
+}
+
+
+impl MarkovChain {
+    pub fn new(board: &Board) -> Self {
+        let size = usize::from(board.size());
+        let mut matrix = vec![vec![0.0; size + 1]; size + 1];
+        for (from, row) in matrix.iter_mut().enumerate().take(size) {
+            for roll in 1..=6 {
+                let landed = from + roll;
+                let to = if landed > size {
+                    from
+                } else {
+                    usize::from(board.destination(landed as u8))
+                };
+                row[to] += 1.0 / 6.0;
+            }
+        }
+        matrix[size][size] = 1.0;
+        MarkovChain { matrix }
+    }
+
+    /// Full transition matrix, indexed by `[from][to]`.
+    pub fn matrix(&self) -> &[Vec<f64>] {
+        &self.matrix
+    }
+
+    /// Probability of moving from one square to another in a single roll.
+    pub fn transition(&self, from: u8, to: u8) -> f64 {
+        self.matrix[usize::from(from)][usize::from(to)]
+    }
+
+    /// Expected number of rolls to finish a game, starting off the board.
+    ///
+    /// Solves `(I - Q) t = 1`, where `Q` is the matrix without its final,
+    /// absorbing, square. Returns `None` if there's any square from which
+    /// the finish can never be reached, which `Board` already rules out.
+    pub fn expected_rolls(&self) -> Option<f64> {
+        let last = self.matrix.len() - 1;
+
+        // Augmented matrix [I - Q | 1]
+        let mut system: Vec<Vec<f64>> = (0..last)
+            .map(|i| {
+                let mut row: Vec<f64> = (0..last)
+                    .map(|j| f64::from(u8::from(i == j)) - self.matrix[i][j])
+                    .collect();
+                row.push(1.0);
+                row
+            })
+            .collect();
+
+        // Gaussian elimination with partial pivoting
+        for column in 0..last {
+            let pivot = (column..last)
+                .max_by(|&a, &b| system[a][column].abs().total_cmp(&system[b][column].abs()))?;
+            if system[pivot][column].abs() < 1e-12 {
+                return None;
+            }
+            system.swap(column, pivot);
+            let (above, below) = system.split_at_mut(column + 1);
+            let pivot_row = &above[column];
+            for row in below {
+                let factor = row[column] / pivot_row[column];
+                if factor != 0.0 {
+                    for (value, pivot_value) in row.iter_mut().zip(pivot_row).skip(column) {
+                        *value -= factor * pivot_value;
+                    }
+                }
+            }
+        }
+
+        // Back substitution, only as far as the starting square
+        let mut solution = vec![0.0; last];
+        for i in (0..last).rev() {
+            let sum: f64 = (i + 1..last).map(|j| system[i][j] * solution[j]).sum();
+            solution[i] = (system[i][last] - sum) / system[i][i];
+        }
+        solution.first().copied()
+    }
+
+    /// Probability of a game taking exactly `n` rolls, for every `n` from
+    /// zero up to `max_rolls`.
+    pub fn length_distribution(&self, max_rolls: usize) -> Vec<f64> {
+        let last = self.matrix.len() - 1;
+        let mut state = vec![0.0; last + 1];
+        state[0] = 1.0;
+        let mut distribution = vec![0.0];
+        for _ in 0..max_rolls {
+            let mut next = vec![0.0; last + 1];
+            for (from, &p) in state.iter().enumerate().take(last) {
+                if p > 0.0 {
+                    for (to, &q) in self.matrix[from].iter().enumerate() {
+                        next[to] += p * q;
+                    }
+                }
+            }
+            distribution.push(next[last]);
+            next[last] = 0.0;
+            state = next;
+        }
+        distribution
+    }
+
+    /// Probability of finishing a game in `rolls` rolls or fewer.
+    pub fn finish_within(&self, rolls: usize) -> f64 {
+        self.length_distribution(rolls).iter().sum()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    use crate::play_games;
+
+    #[test]
+    fn rows_sum_to_one() {
+        let chain = MarkovChain::new(&Board::default());
+        for row in chain.matrix() {
+            assert!((row.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        }
+        assert_eq!(chain.transition(0, 38), 1.0 / 6.0);
+        assert_eq!(chain.transition(0, 1), 0.0);
+        assert!((chain.transition(99, 99) - 5.0 / 6.0).abs() < 1e-12);
+        assert_eq!(chain.transition(100, 100), 1.0);
+    }
+
+    #[test]
+    fn single_square() {
+        // Must roll exactly one, so a geometric distribution
+        let board = Board::new("Tiny", 1, &[], &[]).unwrap();
+        let chain = MarkovChain::new(&board);
+        assert!((chain.expected_rolls().unwrap() - 6.0).abs() < 1e-9);
+        let lengths = chain.length_distribution(3);
+        assert_eq!(lengths[0], 0.0);
+        assert!((lengths[1] - 1.0 / 6.0).abs() < 1e-12);
+        assert!((lengths[3] - 25.0 / 216.0).abs() < 1e-12);
+        assert!((chain.finish_within(2) - 11.0 / 36.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn bundled_boards_finish() {
+        // Boards are checked to be finishable, so the system always solves
+        for name in Board::bundled_names() {
+            let board = Board::bundled(name).unwrap();
+            let expected = MarkovChain::new(&board).expected_rolls();
+            assert!(expected.is_some_and(|rolls| rolls > 0.0), "{name}");
+        }
+    }
+
+    #[test]
+    fn distribution_sums_to_one() {
+        let chain = MarkovChain::new(&Board::default());
+        let total: f64 = chain.length_distribution(2_000).iter().sum();
+        assert!((total - 1.0).abs() < 1e-9);
+
+        // Mean of the distribution agrees with the linear solution
+        let mean: f64 = chain.length_distribution(2_000).iter()
+            .enumerate()
+            .map(|(n, p)| n as f64 * p)
+            .sum();
+        assert!((mean - chain.expected_rolls().unwrap()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn matches_simulation() {
+        let board = Board::default();
+        let chain = MarkovChain::new(&board);
+        let result = play_games(&board, 200_000, Duration::from_secs(3600), Some(1));
+
+        let expected = chain.expected_rolls().unwrap();
+        assert!((result.mean() - expected).abs() < 0.3, "{} vs {expected}", result.mean());
+
+        let lengths = chain.length_distribution(500);
+        let mut exact = 0.0;
+        let mut seen = 0;
+        for (n, p) in lengths.iter().enumerate().take(201) {
+            exact += p;
+            seen += result.counts().get(&n).copied().unwrap_or(0);
+            let simulated = seen as f64 / result.num_games() as f64;
+            assert!((simulated - exact).abs() < 0.01, "within {n}: {simulated} vs {exact}");
+        }
+    }
+}
@@ -0,0 +1,376 @@
+//! Games for several players, under different house rules.
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+use rand::rngs::SmallRng;
+
+use crate::{run_batches, Board, MAX_ROLLS, ROLLS_PER_CHECK};
+
+
+/// What happens when a roll would take a player past the final square.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Overshoot {
+    /// Stay where you are. Finishing needs an exact roll.
+    #[default]
+    Stay,
+
+    /// Count the rest of the roll back down from the final square.
+    /// Finishing needs an exact roll.
+    Bounce,
+
+    /// Any roll reaching the final square finishes.
+    Finish,
+}
+
+
+/// House rules for a game.
+///
+/// The default rules are those of `play_game`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Rules {
+    pub overshoot: Overshoot,
+
+    /// Rolling a six earns another roll.
+    pub extra_turn_on_six: bool,
+
+    /// A player's third six in a row sends them back to the start, instead
+    /// of moving them.
+    pub three_sixes_to_start: bool,
+}
+
+
+impl Rules {
+    /// Every combination of rules.
+    pub fn all() -> Vec<Rules> {
+        let mut all = Vec::new();
+        for overshoot in [Overshoot::Stay, Overshoot::Bounce, Overshoot::Finish] {
+            for extra_turn_on_six in [false, true] {
+                for three_sixes_to_start in [false, true] {
+                    all.push(Rules { overshoot, extra_turn_on_six, three_sixes_to_start });
+                }
+            }
+        }
+        all
+    }
+
+    /// Where a player on `place` ends up after a roll, following any snakes
+    /// and ladders.
+    pub fn advance(&self, board: &Board, place: u8, roll: u8) -> u8 {
+        let size = u16::from(board.size());
+        let landed = u16::from(place) + u16::from(roll);
+        let landed = if landed <= size {
+            landed
+        } else {
+            match self.overshoot {
+                Overshoot::Stay => return place,
+                Overshoot::Bounce => size.saturating_sub(landed - size),
+                Overshoot::Finish => size,
+            }
+        };
+        board.destination(landed as u8)
+    }
+}
+
+
+impl fmt::Display for Rules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let overshoot = match self.overshoot {
+            Overshoot::Stay => "stay on overshoot",
+            Overshoot::Bounce => "bounce on overshoot",
+            Overshoot::Finish => "no exact finish",
+        };
+        write!(f, "{overshoot}")?;
+        if self.extra_turn_on_six {
+            write!(f, ", extra turn on six")?;
+        }
+        if self.three_sixes_to_start {
+            write!(f, ", three sixes to start")?;
+        }
+        Ok(())
+    }
+}
+
+
+/// Single player's progress through a game.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Player {
+    pub place: u8,
+
+    /// Sixes rolled in a row.
+    pub sixes: u8,
+}
+
+
+impl Player {
+    /// Move the player for a single roll. Returns true if the same
+    /// player should roll again.
+    pub fn play_roll(&mut self, board: &Board, rules: &Rules, roll: u8) -> bool {
+        self.sixes = if roll == 6 { self.sixes + 1 } else { 0 };
+        if rules.three_sixes_to_start && self.sixes == 3 {
+            self.place = 0;
+            self.sixes = 0;
+            return false;
+        }
+        self.place = rules.advance(board, self.place, roll);
+        rules.extra_turn_on_six && roll == 6 && self.place != board.size()
+    }
+}
+
+
+/// Results of many games with the same number of players and rules.
+#[derive(Clone, Debug)]
+pub struct MultiplayerResult {
+    rules: Rules,
+    num_games: usize,
+
+    /// Games won by each seat, in order of play.
+    wins: Vec<usize>,
+
+    /// Rolls made by all players, in all games.
+    total_rolls: usize,
+}
+
+
+impl MultiplayerResult {
+    fn new(rules: Rules, players: usize) -> Self {
+        MultiplayerResult { rules, num_games: 0, wins: vec![0; players], total_rolls: 0 }
+    }
+
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    pub fn num_games(&self) -> usize {
+        self.num_games
+    }
+
+    pub fn wins(&self) -> &[usize] {
+        &self.wins
+    }
+
+    /// Chance of the player in `seat` winning, with seat zero going first.
+    pub fn win_probability(&self, seat: usize) -> f64 {
+        self.wins[seat] as f64 / self.num_games as f64
+    }
+
+    /// Average rolls per game, by all players together.
+    pub fn mean_rolls(&self) -> f64 {
+        self.total_rolls as f64 / self.num_games as f64
+    }
+
+    fn merge(&mut self, other: MultiplayerResult) {
+        self.num_games += other.num_games;
+        self.total_rolls += other.total_rolls;
+        for (wins, other) in self.wins.iter_mut().zip(other.wins) {
+            *wins += other;
+        }
+    }
+}
+
+
+/// Play many games for the given number of players, across all cores.
+///
+/// Limits and seeding work as for `play_games`, and games given up aren't
+/// counted.
+pub fn play_multiplayer(
+    board: &Board,
+    rules: Rules,
+    players: usize,
+    num_games: usize,
+    time_limit: Duration,
+    seed: Option<u64>,
+) -> MultiplayerResult {
+    assert!(players > 0, "a game needs at least one player");
+    let batches = run_batches(num_games, time_limit, seed, |rng, size, deadline| {
+        let mut result = MultiplayerResult::new(rules, players);
+        let mut seats = vec![Player::default(); players];
+        for _ in 0..size {
+            if Instant::now() >= deadline {
+                break;
+            }
+            let game = play_multiplayer_game(rng, board, &rules, &mut seats, deadline);
+            let Some((winner, rolls)) = game else {
+                continue;
+            };
+            result.wins[winner] += 1;
+            result.total_rolls += rolls;
+            result.num_games += 1;
+        }
+        result
+    });
+    let mut result = MultiplayerResult::new(rules, players);
+    for batch in batches {
+        result.merge(batch);
+    }
+    result
+}
+
+
+/// Play one game, returning the winning seat and the total number of rolls,
+/// or `None` if the game was given up, as for `play_game`.
+fn play_multiplayer_game(
+    rng: &mut SmallRng,
+    board: &Board,
+    rules: &Rules,
+    seats: &mut [Player],
+    deadline: Instant,
+) -> Option<(usize, usize)> {
+    seats.fill(Player::default());
+    let mut rolls = 0;
+    loop {
+        for (seat, player) in seats.iter_mut().enumerate() {
+            loop {
+                let roll = rng.gen_range(1..=6);
+                rolls += 1;
+                let again = player.play_roll(board, rules, roll);
+                if player.place == board.size() {
+                    return Some((seat, rolls));
+                }
+
+                // Give up on endless games
+                if rolls >= MAX_ROLLS
+                    || (rolls.is_multiple_of(ROLLS_PER_CHECK) && Instant::now() >= deadline)
+                {
+                    return None;
+                }
+                if !again {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::SeedableRng;
+
+    use crate::tests::{far_future, gated_board};
+    use crate::{MarkovChain, BATCH_SIZE};
+
+    const FOREVER: Duration = Duration::from_secs(3600);
+
+    fn short_board() -> Board {
+        Board::new("Short", 10, &[(9, 2)], &[(3, 5)]).unwrap()
+    }
+
+    #[test]
+    fn overshoot() {
+        let board = short_board();
+        let stay = Rules::default();
+        let bounce = Rules { overshoot: Overshoot::Bounce, ..Rules::default() };
+        let finish = Rules { overshoot: Overshoot::Finish, ..Rules::default() };
+        assert_eq!(stay.advance(&board, 8, 2), 10);
+        assert_eq!(stay.advance(&board, 8, 4), 8);
+        assert_eq!(bounce.advance(&board, 8, 3), 9 - 7);
+        assert_eq!(bounce.advance(&board, 8, 4), 8);
+        assert_eq!(finish.advance(&board, 8, 6), 10);
+        assert_eq!(finish.advance(&board, 1, 2), 5);
+    }
+
+    #[test]
+    fn sixes() {
+        let board = Board::new("Long", 100, &[], &[]).unwrap();
+        let rules = Rules { extra_turn_on_six: true, three_sixes_to_start: true, ..Rules::default() };
+        let mut player = Player { place: 20, sixes: 0 };
+        assert!(player.play_roll(&board, &rules, 6));
+        assert!(player.play_roll(&board, &rules, 6));
+        assert!(!player.play_roll(&board, &rules, 6));
+        assert_eq!(player, Player { place: 0, sixes: 0 });
+
+        assert!(!player.play_roll(&board, &Rules::default(), 6));
+        assert_eq!(player, Player { place: 6, sixes: 1 });
+        assert!(!player.play_roll(&board, &Rules::default(), 2));
+        assert_eq!(player.sixes, 0);
+    }
+
+    #[test]
+    fn no_extra_roll_after_finishing() {
+        let board = short_board();
+        let rules = Rules { extra_turn_on_six: true, ..Rules::default() };
+        let mut player = Player { place: 4, sixes: 0 };
+        assert!(!player.play_roll(&board, &rules, 6));
+        assert_eq!(player.place, 10);
+    }
+
+    #[test]
+    fn all_rules() {
+        let all = Rules::all();
+        assert_eq!(all.len(), 12);
+        assert_eq!(all[0], Rules::default());
+        assert_eq!(all[0].to_string(), "stay on overshoot");
+        assert_eq!(all[11].to_string(), "no exact finish, extra turn on six, three sixes to start");
+    }
+
+    #[test]
+    fn solitaire_matches_markov_chain() {
+        let board = Board::default();
+        let result = play_multiplayer(&board, Rules::default(), 1, 100_000, FOREVER, Some(3));
+        let expected = MarkovChain::new(&board).expected_rolls().unwrap();
+        assert_eq!(result.win_probability(0), 1.0);
+        assert!((result.mean_rolls() - expected).abs() < 0.5);
+    }
+
+    #[test]
+    fn first_seat_advantage() {
+        let board = Board::default();
+        for rules in Rules::all() {
+            let result = play_multiplayer(&board, rules, 3, 20_000, FOREVER, Some(5));
+            let total: f64 = (0..3).map(|seat| result.win_probability(seat)).sum();
+            assert!((total - 1.0).abs() < 1e-12);
+            assert!(result.win_probability(0) > result.win_probability(2), "{rules}");
+        }
+    }
+
+    #[test]
+    fn seeded_runs_match() {
+        let board = Board::default();
+        let rules = Rules { overshoot: Overshoot::Bounce, ..Rules::default() };
+        let a = play_multiplayer(&board, rules, 2, 25_000, FOREVER, Some(9));
+        let b = play_multiplayer(&board, rules, 2, 25_000, FOREVER, Some(9));
+        assert_eq!(a.wins(), b.wins());
+        assert_eq!(a.mean_rolls(), b.mean_rolls());
+    }
+
+    #[test]
+    fn game_has_a_winner() {
+        let board = short_board();
+        let mut rng = SmallRng::seed_from_u64(1);
+        let mut seats = vec![Player::default(); 4];
+        for rules in Rules::all() {
+            let game = play_multiplayer_game(&mut rng, &board, &rules, &mut seats, far_future());
+            let (winner, rolls) = game.unwrap();
+            assert_eq!(seats[winner].place, 10);
+            assert!(rolls > 0);
+        }
+    }
+
+    #[test]
+    fn endless_games_are_abandoned() {
+        let board = gated_board();
+        let mut rng = SmallRng::seed_from_u64(1);
+        let mut seats = vec![Player::default(); 3];
+        for rules in Rules::all() {
+            let game = play_multiplayer_game(&mut rng, &board, &rules, &mut seats, far_future());
+            assert_eq!(game, None, "{rules}");
+            let game = play_multiplayer_game(&mut rng, &board, &rules, &mut seats, Instant::now());
+            assert_eq!(game, None, "{rules}");
+        }
+    }
+
+    #[test]
+    fn time_limit_stops_part_way_through_a_batch() {
+        let board = gated_board();
+        let timer = Instant::now();
+        let time_limit = Duration::from_millis(50);
+        let result = play_multiplayer(&board, Rules::default(), 2, usize::MAX, time_limit, Some(1));
+        assert!(timer.elapsed() < Duration::from_secs(5));
+        assert!(result.num_games() < BATCH_SIZE);
+        assert_eq!(result.wins().iter().sum::<usize>(), result.num_games());
+    }
+}
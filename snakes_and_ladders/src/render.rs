@@ -0,0 +1,121 @@
+//! Draw a board as text, with players on it.
+//!
+//! Squares snake back and forth across the board, boustrophedon-style: the
+//! bottom row runs left to right from square one, the row above it right to
+//! left, and so on up to the final square.
+
+use crate::Board;
+
+
+/// Squares in each row of the board.
+pub const ROW_LENGTH: usize = 10;
+
+/// Marks for each player, in seat order.
+const PLAYER_MARKS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// Most players shown on a single square.
+const MARKS_PER_SQUARE: usize = 3;
+
+
+impl Board {
+    /// Draw the board, with players on their squares.
+    ///
+    /// `places` gives the square of each player, in seat order, who are
+    /// marked A, B, C, and so on. Each square shows its number, then `^` if a
+    /// ladder starts there or `v` for a snake, then any players on it.
+    pub fn render(&self, places: &[u8]) -> String {
+        let size = usize::from(self.size());
+        let rows = size.div_ceil(ROW_LENGTH);
+        let mut text = String::new();
+        for row in (0..rows).rev() {
+            let mut line = String::new();
+            for column in 0..ROW_LENGTH {
+                let offset = if row % 2 == 0 { column } else { ROW_LENGTH - 1 - column };
+                let square = row * ROW_LENGTH + offset + 1;
+                if square > size {
+                    line.push_str(&" ".repeat(2 + MARKS_PER_SQUARE + 3));
+                } else {
+                    line.push_str(&self.render_square(square as u8, places));
+                }
+            }
+            text.push_str(line.trim_end());
+            text.push('\n');
+        }
+
+        // Players waiting to start
+        let waiting = marks(0, places);
+        if !waiting.is_empty() {
+            text.push_str(&format!("Start: {waiting}\n"));
+        }
+        text
+    }
+
+    /// Single square, padded to its full width.
+    fn render_square(&self, square: u8, places: &[u8]) -> String {
+        let jump = self.destination(square);
+        let mark = if jump > square {
+            '^'
+        } else if jump < square {
+            'v'
+        } else {
+            ' '
+        };
+        let mut players = marks(square, places);
+        players.truncate(MARKS_PER_SQUARE);
+        format!(" {square:>3}{mark}{players:<MARKS_PER_SQUARE$}")
+    }
+
+    /// List of every snake and ladder, for a key to the board.
+    pub fn legend(&self) -> String {
+        let jumps = |list: &[(u8, u8)]| -> String {
+            let mut list = list.to_vec();
+            list.sort_unstable();
+            list.iter().map(|(from, to)| format!("{from}->{to}")).collect::<Vec<_>>().join(" ")
+        };
+        format!("Ladders: {}\nSnakes:  {}\n", jumps(self.ladders()), jumps(self.snakes()))
+    }
+}
+
+
+/// Marks of all the players on a square.
+fn marks(square: u8, places: &[u8]) -> String {
+    places
+        .iter()
+        .zip(PLAYER_MARKS.iter().cycle())
+        .filter(|&(&place, _)| place == square)
+        .map(|(_, &mark)| char::from(mark))
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boustrophedon() {
+        let board = Board::new("Small", 24, &[(22, 3)], &[(2, 15)]).unwrap();
+        let text = board.render(&[0, 15, 15, 24]);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines, [
+            "  21      22v     23      24 D",
+            "  20      19      18      17      16      15 BC   14      13      12      11",
+            "   1       2^      3       4       5       6       7       8       9      10",
+            "Start: A",
+        ]);
+    }
+
+    #[test]
+    fn crowded_square() {
+        let board = Board::new("Tiny", 10, &[], &[]).unwrap();
+        let text = board.render(&[5, 5, 5, 5, 5]);
+        assert!(text.contains("   5 ABC "));
+        assert!(!text.contains("Start"));
+    }
+
+    #[test]
+    fn legend() {
+        let board = Board::new("Small", 24, &[(22, 3), (20, 1)], &[(2, 15)]).unwrap();
+        assert_eq!(board.legend(), "Ladders: 2->15\nSnakes:  20->1 22->3\n");
+    }
+}